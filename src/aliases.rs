@@ -0,0 +1,223 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use console::style;
+use dialoguer::{theme::ColorfulTheme, Input};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use crate::scanner::get_fingerprint;
+
+/// One `[[alias]]` entry of the alias file as written by hand.
+#[derive(Debug, Deserialize)]
+struct AliasEntry {
+    /// Regex matched case-insensitively against the parsed title, and against `Title (Year)`
+    #[serde(default, rename = "match")]
+    pattern: Option<String>,
+    /// Fingerprint of the parsed title: lowercase letters and digits only, e.g. `theofficeus`
+    #[serde(default)]
+    fingerprint: Option<String>,
+    title: String,
+    #[serde(default)]
+    year: Option<u32>,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AliasFile {
+    #[serde(default)]
+    alias: Vec<AliasEntry>,
+}
+
+/// The canonical name an alias maps a parsed title to.
+#[derive(Debug, Clone)]
+pub struct Canonical {
+    pub title: String,
+    pub year: Option<u32>,
+    /// Free-form category such as `Anime` or `Documentary`, used for routing
+    pub category: Option<String>,
+}
+
+enum Matcher {
+    Pattern(Regex),
+    Fingerprint(String),
+}
+
+/// Title aliases loaded from `aliases.toml`, tried in file order.
+#[derive(Default)]
+pub struct Aliases {
+    entries: Vec<(Matcher, Canonical)>,
+}
+
+impl Aliases {
+    /// Loads the alias file. A missing file gives an empty list; unreadable TOML, entries
+    /// without `match` or `fingerprint` and invalid regexes are errors.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let file: AliasFile = toml::from_str(&content).with_context(|| format!("cannot parse {}", path.display()))?;
+
+        let mut entries = Vec::new();
+        for entry in file.alias {
+            let matcher = match (entry.pattern, entry.fingerprint) {
+                (Some(pattern), _) => Matcher::Pattern(
+                    RegexBuilder::new(&pattern)
+                        .case_insensitive(true)
+                        .build()
+                        .with_context(|| format!("invalid alias pattern for \"{}\"", entry.title))?,
+                ),
+                (None, Some(fingerprint)) => Matcher::Fingerprint(get_fingerprint(&fingerprint)),
+                (None, None) => bail!("alias \"{}\" needs a `match` or `fingerprint`", entry.title),
+            };
+            entries.push((matcher, Canonical { title: entry.title, year: entry.year, category: entry.category }));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// The first alias matching a parsed title and year.
+    pub fn lookup(&self, title: &str, year: Option<&str>) -> Option<&Canonical> {
+        let with_year = year.map(|y| format!("{} ({})", title, y));
+        let fingerprint = get_fingerprint(with_year.as_deref().unwrap_or(title));
+        let bare_fingerprint = get_fingerprint(title);
+
+        self.entries.iter()
+            .find(|(matcher, _)| match matcher {
+                Matcher::Pattern(re) => re.is_match(title) || with_year.as_deref().is_some_and(|t| re.is_match(t)),
+                Matcher::Fingerprint(fp) => *fp == fingerprint || *fp == bare_fingerprint,
+            })
+            .map(|(_, canonical)| canonical)
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Appends a fingerprint alias for a parsed title (and year) to the alias file, keeping
+/// whatever was written there by hand.
+fn append_alias(path: &Path, parsed_title: &str, parsed_year: Option<&str>, canonical: &Canonical) -> Result<()> {
+    let parsed = match parsed_year {
+        Some(year) => format!("{} ({})", parsed_title, year),
+        None => parsed_title.to_string(),
+    };
+    let mut entry = format!(
+        "\n[[alias]]\nfingerprint = {}\ntitle = {}\n",
+        toml_string(&get_fingerprint(&parsed)),
+        toml_string(&canonical.title)
+    );
+    if let Some(year) = canonical.year {
+        entry.push_str(&format!("year = {}\n", year));
+    }
+    if let Some(category) = &canonical.category {
+        entry.push_str(&format!("category = {}\n", toml_string(category)));
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(entry.as_bytes())?;
+    Ok(())
+}
+
+/// Asks for a canonical name for each parsed title without an alias and appends the answers
+/// to the alias file. `Title (Year)` sets the year too; an empty answer leaves the title alone.
+/// Returns the number of aliases added.
+pub fn prompt_aliases(path: &Path, titles: &[(String, Option<String>)]) -> Result<usize> {
+    let theme = ColorfulTheme::default();
+    let year_re = Regex::new(r"^(.*?)\s*\(((?:19|20)\d{2})\)$").unwrap();
+    let mut added = 0;
+
+    println!("{}", style("Name the titles below to merge their folders; leave empty to keep a title as parsed.").dim());
+    for (title, year) in titles {
+        let shown = match year {
+            Some(year) => format!("{} ({})", title, year),
+            None => title.clone(),
+        };
+        let answer: String = Input::with_theme(&theme)
+            .with_prompt(format!("Canonical title for \"{}\"", shown))
+            .allow_empty(true)
+            .interact_text()?;
+        let answer = answer.trim();
+        if answer.is_empty() {
+            continue;
+        }
+
+        let category: String = Input::with_theme(&theme)
+            .with_prompt("Category (optional)")
+            .allow_empty(true)
+            .interact_text()?;
+
+        let (canonical_title, canonical_year) = match year_re.captures(answer) {
+            Some(caps) => (caps[1].to_string(), caps[2].parse().ok()),
+            None => (answer.to_string(), None),
+        };
+        let category = category.trim();
+        let canonical = Canonical {
+            title: canonical_title,
+            year: canonical_year,
+            category: (!category.is_empty()).then(|| category.to_string()),
+        };
+
+        append_alias(path, title, year.as_deref(), &canonical)?;
+        added += 1;
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(content: &str) -> Result<Aliases> {
+        let path = std::env::temp_dir().join(format!("mkv_pro_manager_aliases_{}_{}.toml", std::process::id(), content.len()));
+        fs::write(&path, content).unwrap();
+        let aliases = Aliases::load(&path);
+        fs::remove_file(&path).unwrap();
+        aliases
+    }
+
+    #[test]
+    fn pattern_and_fingerprint_aliases() {
+        let aliases = aliases(r#"
+            [[alias]]
+            match = "^the office( us)?$"
+            title = "The Office"
+            year = 2005
+            category = "Comedy"
+
+            [[alias]]
+            fingerprint = "The Office (2001)"
+            title = "The Office UK"
+        "#).unwrap();
+
+        let found = aliases.lookup("The Office US", None).unwrap();
+        assert_eq!((found.title.as_str(), found.year, found.category.as_deref()), ("The Office", Some(2005), Some("Comedy")));
+        assert_eq!(aliases.lookup("the office", Some("2005")).unwrap().title, "The Office");
+        // The fingerprint includes the year, so only the 2001 show maps to the UK version
+        assert_eq!(aliases.lookup("The.Office", Some("2001")).unwrap().title, "The Office UK");
+        assert!(aliases.lookup("The.Office", Some("2010")).is_none());
+        assert!(aliases.lookup("The Office Party", None).is_none());
+    }
+
+    #[test]
+    fn fingerprint_matches_with_or_without_year() {
+        let aliases = aliases(r#"
+            [[alias]]
+            fingerprint = "theofficeus"
+            title = "The Office"
+        "#).unwrap();
+        assert_eq!(aliases.lookup("The Office (US)", Some("2005")).unwrap().title, "The Office");
+        assert_eq!(aliases.lookup("The.Office.US", None).unwrap().title, "The Office");
+        assert!(aliases.lookup("The Office", None).is_none());
+    }
+
+    #[test]
+    fn invalid_entries_are_errors() {
+        assert!(aliases("[[alias]]\ntitle = \"X\"\n").is_err());
+        assert!(aliases("[[alias]]\nmatch = \"(\"\ntitle = \"X\"\n").is_err());
+        assert!(Aliases::load(Path::new("/nonexistent/aliases.toml")).unwrap().lookup("X", None).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use rayon::prelude::*;
+use crate::config::Config;
+use crate::scanner::{AssetIndex, FoundAssets};
+
+/// An asset that matched more than one video and was given to the best match only.
+#[derive(Debug)]
+pub struct AssetConflict {
+    pub asset: PathBuf,
+    pub winner: PathBuf,
+    pub score: u32,
+    pub losers: Vec<PathBuf>,
+}
+
+/// The global result of matching every asset against every video before processing.
+#[derive(Debug, Default)]
+pub struct AssetPlan {
+    assignments: HashMap<PathBuf, FoundAssets>,
+    pub conflicts: Vec<AssetConflict>,
+    pub orphans: Vec<PathBuf>,
+}
+
+impl AssetPlan {
+    /// Returns the assets assigned exclusively to this video.
+    pub fn assets_for(&self, video: &Path) -> FoundAssets {
+        self.assignments.get(video).cloned().unwrap_or_default()
+    }
+}
+
+/// Matches all videos against their candidate assets and gives each asset to exactly one video.
+///
+/// `videos` is expected to be sorted so that ties are resolved deterministically in favour
+/// of the first video. Indexed assets that no video claims are reported as orphans.
+pub fn build_asset_plan(videos: &[PathBuf], index: &AssetIndex, config: &Config) -> AssetPlan {
+    let candidates: Vec<_> = videos.par_iter()
+        .map(|video| index.find_matching_assets(video, config))
+        .collect();
+
+    // asset -> [(video index, score)], in video order
+    let mut claims: HashMap<PathBuf, Vec<(usize, u32)>> = HashMap::new();
+    let mut companions: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for (idx, matches) in candidates.into_iter().enumerate() {
+        for m in matches {
+            if !m.companions.is_empty() {
+                companions.insert(m.path.clone(), m.companions);
+            }
+            claims.entry(m.path).or_default().push((idx, m.score));
+        }
+    }
+
+    let mut plan = AssetPlan::default();
+    let mut claimed: Vec<_> = claims.into_iter().collect();
+    claimed.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (asset, claimants) in claimed {
+        // max_by_key returns the last maximum; iterate in reverse so the first video wins ties
+        let &(winner_idx, score) = claimants.iter().rev().max_by_key(|(_, s)| *s).unwrap();
+        let winner = &videos[winner_idx];
+
+        if claimants.len() > 1 {
+            plan.conflicts.push(AssetConflict {
+                asset: asset.clone(),
+                winner: winner.clone(),
+                score,
+                losers: claimants.iter()
+                    .filter(|(idx, _)| *idx != winner_idx)
+                    .map(|(idx, _)| videos[*idx].clone())
+                    .collect(),
+            });
+        }
+
+        let found = plan.assignments.entry(winner.clone()).or_default();
+        found.companions.extend(companions.remove(&asset).unwrap_or_default());
+        found.push(asset, config);
+    }
+
+    // Fonts and folder posters are shared by every video that can see them, so they bypass the exclusive assignment
+    for video in videos {
+        let fonts = index.find_fonts(video, config);
+        let covers = index.find_covers(video, config);
+        if !fonts.is_empty() || !covers.is_empty() {
+            let found = plan.assignments.entry(video.clone()).or_default();
+            found.fonts = fonts;
+            found.covers = covers;
+        }
+    }
+
+    let assigned: HashSet<&Path> = plan.assignments.values()
+        .flat_map(|a| a.subtitles.iter().chain(&a.audios).chain(&a.chapters))
+        .map(|p| p.as_path())
+        .collect();
+    let mut orphans: Vec<PathBuf> = index.all_assets()
+        .filter(|a| !assigned.contains(a))
+        .map(Path::to_path_buf)
+        .collect();
+    orphans.sort();
+    plan.orphans = orphans;
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assign(videos: &[&str], assets: &[&str]) -> AssetPlan {
+        let config = Config::default();
+        let videos: Vec<PathBuf> = videos.iter().map(PathBuf::from).collect();
+        let assets: Vec<PathBuf> = assets.iter().map(PathBuf::from).collect();
+        let index = AssetIndex::build(&assets, &config);
+        build_asset_plan(&videos, &index, &config)
+    }
+
+    fn subtitles(plan: &AssetPlan, video: &str) -> Vec<PathBuf> {
+        plan.assets_for(Path::new(video)).subtitles
+    }
+
+    #[test]
+    fn best_match_wins() {
+        let plan = assign(&["/in/Movie.mkv", "/in/Movie.Extended.mkv"], &["/in/Movie.Extended.srt"]);
+        assert_eq!(subtitles(&plan, "/in/Movie.Extended.mkv"), vec![PathBuf::from("/in/Movie.Extended.srt")]);
+        assert!(subtitles(&plan, "/in/Movie.mkv").is_empty());
+        assert_eq!(plan.conflicts.len(), 1);
+        assert_eq!(plan.conflicts[0].losers, vec![PathBuf::from("/in/Movie.mkv")]);
+    }
+
+    #[test]
+    fn first_video_wins_ties() {
+        let plan = assign(&["/in/Movie.A.mkv", "/in/Movie.B.mkv"], &["/in/Movie.srt"]);
+        assert_eq!(subtitles(&plan, "/in/Movie.A.mkv"), vec![PathBuf::from("/in/Movie.srt")]);
+        assert!(subtitles(&plan, "/in/Movie.B.mkv").is_empty());
+    }
+
+    #[test]
+    fn episode_tags_must_agree() {
+        let plan = assign(&["/in/Show.S01E01.mkv", "/in/Show.S01E11.mkv"], &["/in/Show.S01E1.srt"]);
+        assert_eq!(subtitles(&plan, "/in/Show.S01E01.mkv"), vec![PathBuf::from("/in/Show.S01E1.srt")]);
+        assert!(subtitles(&plan, "/in/Show.S01E11.mkv").is_empty());
+
+        let plan = assign(&["/in/Show.S01E02.mkv", "/in/Show.S01E10.1080p.mkv"], &["/in/Show.S01E1.srt"]);
+        assert!(subtitles(&plan, "/in/Show.S01E10.1080p.mkv").is_empty());
+        assert_eq!(plan.orphans, vec![PathBuf::from("/in/Show.S01E1.srt")]);
+    }
+
+    #[test]
+    fn vobsub_carries_its_sub() {
+        let plan = assign(&["/in/Movie.mkv"], &["/in/Movie.idx", "/in/Movie.sub"]);
+        let found = plan.assets_for(Path::new("/in/Movie.mkv"));
+        assert_eq!(found.subtitles, vec![PathBuf::from("/in/Movie.idx")]);
+        assert_eq!(found.companions, vec![PathBuf::from("/in/Movie.sub")]);
+        assert!(plan.orphans.is_empty());
+    }
+
+    #[test]
+    fn unclaimed_assets_are_orphans() {
+        let plan = assign(&["/in/Movie.mkv"], &["/in/Movie.en.srt", "/in/Other.srt", "/in/Other.ac3"]);
+        assert_eq!(subtitles(&plan, "/in/Movie.mkv"), vec![PathBuf::from("/in/Movie.en.srt")]);
+        assert_eq!(plan.orphans, vec![PathBuf::from("/in/Other.ac3"), PathBuf::from("/in/Other.srt")]);
+    }
+}
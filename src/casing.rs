@@ -0,0 +1,153 @@
+use regex::Regex;
+use once_cell::sync::Lazy;
+
+/// Words kept lowercase inside a title.
+const SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "nor", "of", "on", "or", "the", "to", "vs", "with",
+];
+
+/// Roman numerals made of I, V and X only, so words like "mix" or "did" are not mistaken for one.
+static ROMAN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^(?:x{0,3})(?:ix|iv|v?i{0,3})$").unwrap());
+
+/// A dotted acronym such as `S.H.I.E.L.D.` or `E.T.`
+static ACRONYM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?:[A-Za-z]\.){2,}[A-Za-z]?$").unwrap());
+
+/// Splits a raw name into words. Dots and dashes separate words, except inside dotted
+/// acronyms: `Agents.of.S.H.I.E.L.D` gives `Agents`, `of`, `S.H.I.E.L.D.`.
+fn split_words(input: &str) -> Vec<String> {
+    let mut words = Vec::new();
+
+    for chunk in input.split([' ', '_']).filter(|c| !c.is_empty()) {
+        if ACRONYM_RE.is_match(chunk) {
+            let dot = if chunk.ends_with('.') { "" } else { "." };
+            words.push(format!("{}{}", chunk.to_uppercase(), dot));
+            continue;
+        }
+
+        // In dot-separated names an acronym shows up as a run of single letters
+        let mut letters = Vec::new();
+        for part in chunk.split(['.', '-']) {
+            if part.len() == 1 && part.chars().all(|c| c.is_ascii_alphabetic()) {
+                letters.push(part);
+                continue;
+            }
+            flush_letters(&mut letters, &mut words);
+            if !part.is_empty() {
+                words.push(part.to_string());
+            }
+        }
+        flush_letters(&mut letters, &mut words);
+    }
+
+    words
+}
+
+fn flush_letters(letters: &mut Vec<&str>, words: &mut Vec<String>) {
+    if letters.len() >= 3 {
+        words.push(format!("{}.", letters.join(".").to_uppercase()));
+    } else {
+        words.extend(letters.iter().map(|l| l.to_string()));
+    }
+    letters.clear();
+}
+
+/// Uppercases the first letter and lowercases the rest, plus the letter after an `O'`-style prefix.
+fn capitalize(word: &str) -> String {
+    let chars: Vec<char> = word.to_lowercase().chars().collect();
+    let name_prefix = chars.len() > 4 && matches!(chars[1], '\'' | '’');
+
+    chars.iter()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            let upper = i == 0 || (i == 2 && name_prefix);
+            if upper { c.to_uppercase().collect::<Vec<_>>() } else { vec![*c] }
+        })
+        .collect()
+}
+
+fn is_all_caps(word: &str) -> bool {
+    word.chars().any(|c| c.is_alphabetic()) && !word.chars().any(|c| c.is_lowercase())
+}
+
+/// Turns a raw file or folder name into a display title.
+///
+/// All-caps words (`CSI`, `MASH`), Roman numerals and dotted acronyms keep their capitals,
+/// words with inner capitals (`iCarly`) are left alone and small words are lowercased unless
+/// they start or end the title. A name written entirely in capitals is treated as shouting
+/// when it has a word longer than four letters, so `THE.OFFICE` still becomes `The Office`.
+/// Words in `exceptions` are always written exactly as listed there.
+pub fn title_case(input: &str, exceptions: &[String]) -> String {
+    let words: Vec<String> = split_words(input)
+        .into_iter()
+        .filter(|w| !w.starts_with('(') && !w.ends_with(')')) // Ignore trailing (2024) etc if caught
+        .collect();
+
+    let shouting = words.iter().all(|w| is_all_caps(w) || !w.chars().any(|c| c.is_alphabetic()))
+        && words.iter().any(|w| w.chars().filter(|c| c.is_alphabetic()).count() > 4);
+    let last = words.len().saturating_sub(1);
+
+    words.iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let lower = word.to_lowercase();
+            if let Some(exact) = exceptions.iter().find(|e| e.to_lowercase() == lower) {
+                exact.clone()
+            } else if ACRONYM_RE.is_match(word) || !lower.is_empty() && ROMAN_RE.is_match(word) {
+                word.to_uppercase()
+            } else if is_all_caps(word) && word.len() > 1 && !shouting {
+                word.clone()
+            } else if i > 0 && i < last && SMALL_WORDS.contains(&lower.as_str()) {
+                lower
+            } else if word.chars().skip(1).any(|c| c.is_uppercase()) && !is_all_caps(word) {
+                word.clone()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(input: &str) -> String {
+        title_case(input, &[])
+    }
+
+    #[test]
+    fn keeps_acronyms() {
+        assert_eq!(case("CSI NY"), "CSI NY");
+        assert_eq!(case("MASH"), "MASH");
+        assert_eq!(case("Marvel's.Agents.of.S.H.I.E.L.D"), "Marvel's Agents of S.H.I.E.L.D.");
+        assert_eq!(case("Agents of S.H.I.E.L.D."), "Agents of S.H.I.E.L.D.");
+    }
+
+    #[test]
+    fn shouting_names_are_title_cased() {
+        assert_eq!(case("THE.OFFICE"), "The Office");
+    }
+
+    #[test]
+    fn small_words_and_numerals() {
+        assert_eq!(case("the.lord.of.the.rings"), "The Lord of the Rings");
+        assert_eq!(case("rocky ii"), "Rocky II");
+        assert_eq!(case("star wars episode iv a new hope"), "Star Wars Episode IV a New Hope");
+        assert_eq!(case("mix"), "Mix");
+        assert_eq!(case("what are you waiting for"), "What Are You Waiting For");
+    }
+
+    #[test]
+    fn names_and_inner_capitals() {
+        assert_eq!(case("conan o'brien"), "Conan O'Brien");
+        assert_eq!(case("iCarly"), "iCarly");
+    }
+
+    #[test]
+    fn exceptions_win() {
+        let exceptions = vec!["iCarly".to_string(), "deadmau5".to_string()];
+        assert_eq!(title_case("ICARLY", &exceptions), "iCarly");
+        assert_eq!(title_case("Deadmau5.Live", &exceptions), "deadmau5 Live");
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use strsim::normalized_levenshtein;
+use crate::scanner::get_fingerprint;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEpisode {
+    pub season: u32,
+    pub episode: u32,
+    #[serde(default)]
+    pub title: String,
+}
+
+/// A movie or show of the metadata catalog.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CatalogEntry {
+    pub title: String,
+    #[serde(default)]
+    pub year: Option<u32>,
+    /// `movie` or `series`; empty matches both
+    #[serde(default)]
+    pub kind: String,
+    /// Language name or ISO code of the original audio, e.g. `ja`, `jpn` or `Japanese`
+    #[serde(default)]
+    pub original_language: Option<String>,
+    #[serde(default)]
+    pub episodes: Vec<CatalogEpisode>,
+}
+
+impl CatalogEntry {
+    pub fn episode_title(&self, season: u32, episode: u32) -> Option<&str> {
+        self.episodes.iter()
+            .find(|e| e.season == season && e.episode == episode)
+            .map(|e| e.title.as_str())
+            .filter(|t| !t.is_empty())
+    }
+
+    /// Number of episodes listed for a season, `None` if the catalog has none for it.
+    pub fn season_size(&self, season: u32) -> Option<u32> {
+        let count = self.episodes.iter().filter(|e| e.season == season).count() as u32;
+        (count > 0).then_some(count)
+    }
+
+    fn fits(&self, year: Option<u32>, is_series: bool) -> bool {
+        let kind_fits = match self.kind.to_lowercase().as_str() {
+            "movie" | "film" => !is_series,
+            "series" | "show" | "tv" => is_series,
+            _ => true,
+        };
+        // Release years are often off by one against the catalog's premiere date
+        let year_fits = match (self.year, year) {
+            (Some(a), Some(b)) => a.abs_diff(b) <= 1,
+            _ => true,
+        };
+        kind_fits && year_fits
+    }
+}
+
+/// One row of a CSV dump. Rows sharing a title and year make up one entry; rows without
+/// season and episode describe the title only.
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    title: String,
+    #[serde(default)]
+    year: Option<u32>,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    original_language: Option<String>,
+    #[serde(default)]
+    season: Option<u32>,
+    #[serde(default)]
+    episode: Option<u32>,
+    #[serde(default)]
+    episode_title: Option<String>,
+}
+
+fn load_json(path: &Path) -> Result<Vec<CatalogEntry>> {
+    let content = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn load_csv(path: &Path) -> Result<Vec<CatalogEntry>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).flexible(true).from_path(path)?;
+    let mut entries: Vec<CatalogEntry> = Vec::new();
+    let mut index: HashMap<(String, Option<u32>), usize> = HashMap::new();
+
+    for row in reader.deserialize() {
+        let row: CsvRow = row?;
+        let idx = *index.entry((row.title.clone(), row.year)).or_insert_with(|| {
+            entries.push(CatalogEntry {
+                title: row.title.clone(),
+                year: row.year,
+                kind: String::new(),
+                original_language: None,
+                episodes: Vec::new(),
+            });
+            entries.len() - 1
+        });
+
+        let entry = &mut entries[idx];
+        if let Some(kind) = row.kind.filter(|k| !k.is_empty()) {
+            entry.kind = kind;
+        }
+        if let Some(lang) = row.original_language.filter(|l| !l.is_empty()) {
+            entry.original_language = Some(lang);
+        }
+        if let (Some(season), Some(episode)) = (row.season, row.episode) {
+            entry.episodes.push(CatalogEpisode { season, episode, title: row.episode_title.unwrap_or_default() });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// A lookup question: title fingerprint, year and whether a series is wanted.
+type LookupKey = (String, Option<u32>, bool);
+
+/// Titles, years, original languages and episode lists from local JSON or CSV dumps.
+#[derive(Default)]
+pub struct Catalog {
+    entries: Vec<(String, CatalogEntry)>,
+    /// Minimum similarity (0-1) of a fuzzy title match
+    threshold: f64,
+    /// Match per (fingerprint, year, series), since every file of a show asks the same question
+    cache: Mutex<HashMap<LookupKey, Option<usize>>>,
+}
+
+impl Catalog {
+    /// Loads every catalog file; the format follows the extension (`.json` or `.csv`).
+    pub fn load(paths: &[PathBuf], threshold: f64) -> Result<Self> {
+        let mut entries = Vec::new();
+        for path in paths {
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+            let loaded = match ext.as_str() {
+                "json" => load_json(path),
+                "csv" => load_csv(path),
+                _ => bail!("{}: catalogs must be .json or .csv", path.display()),
+            }.with_context(|| format!("cannot load {}", path.display()))?;
+            entries.extend(loaded.into_iter().map(|e| (get_fingerprint(&e.title), e)));
+        }
+
+        Ok(Self { entries, threshold, cache: Mutex::new(HashMap::new()) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The catalog entry for a parsed title: an exact fingerprint match, preferring the same
+    /// year, or else the most similar title above the threshold.
+    pub fn lookup(&self, title: &str, year: Option<u32>, is_series: bool) -> Option<&CatalogEntry> {
+        let fingerprint = get_fingerprint(title);
+        if fingerprint.is_empty() || self.is_empty() {
+            return None;
+        }
+        let key = (fingerprint, year, is_series);
+        if let Some(found) = self.cache.lock().unwrap().get(&key) {
+            return found.map(|idx| &self.entries[idx].1);
+        }
+
+        let fitting = || self.entries.iter().enumerate().filter(|(_, (_, e))| e.fits(year, is_series));
+        let exact = fitting()
+            .filter(|(_, (fp, _))| *fp == key.0)
+            .min_by_key(|(_, (_, e))| e.year.zip(year).map_or(1, |(a, b)| a.abs_diff(b)))
+            .map(|(idx, _)| idx);
+        let found = exact.or_else(|| {
+            fitting()
+                .map(|(idx, (fp, _))| (idx, normalized_levenshtein(fp, &key.0)))
+                .filter(|(_, score)| *score >= self.threshold)
+                .fold(None, |best: Option<(usize, f64)>, (idx, score)| match best {
+                    Some((_, top)) if top >= score => best,
+                    _ => Some((idx, score)),
+                })
+                .map(|(idx, _)| idx)
+        });
+
+        self.cache.lock().unwrap().insert(key, found);
+        found.map(|idx| &self.entries[idx].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("mkv_pro_manager_{}_{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn catalog(json: &str, threshold: f64) -> Catalog {
+        let path = temp_file(&format!("catalog_{}.json", json.len()), json);
+        let catalog = Catalog::load(std::slice::from_ref(&path), threshold).unwrap();
+        fs::remove_file(&path).unwrap();
+        catalog
+    }
+
+    const ENTRIES: &str = r#"[
+        {"title": "Doctor Who", "year": 1963, "kind": "series"},
+        {"title": "Doctor Who", "year": 2005, "kind": "series", "original_language": "en"},
+        {"title": "Spirited Away", "year": 2001, "kind": "movie", "original_language": "ja"}
+    ]"#;
+
+    #[test]
+    fn exact_match_prefers_the_closest_year() {
+        let catalog = catalog(ENTRIES, 0.9);
+        assert_eq!(catalog.lookup("Doctor.Who", Some(2005), true).unwrap().year, Some(2005));
+        assert_eq!(catalog.lookup("doctor who", Some(1964), true).unwrap().year, Some(1963));
+        assert!(catalog.lookup("Doctor Who", None, true).is_some());
+        assert!(catalog.lookup("Doctor Who", Some(2005), false).is_none());
+    }
+
+    #[test]
+    fn years_may_be_off_by_one() {
+        let catalog = catalog(ENTRIES, 0.9);
+        assert!(catalog.lookup("Spirited Away", Some(2002), false).is_some());
+        assert!(catalog.lookup("Spirited Away", Some(2003), false).is_none());
+    }
+
+    #[test]
+    fn fuzzy_match_needs_the_threshold() {
+        let strict = catalog(ENTRIES, 0.9);
+        assert_eq!(strict.lookup("Spirted Away", Some(2001), false).unwrap().title, "Spirited Away");
+        assert!(strict.lookup("Spirit", Some(2001), false).is_none());
+        let loose = catalog(ENTRIES, 0.4);
+        assert!(loose.lookup("Spirit", Some(2001), false).is_some());
+    }
+
+    #[test]
+    fn csv_rows_are_grouped_by_title_and_year() {
+        let path = temp_file("catalog.csv", "title,year,kind,original_language,season,episode,episode_title\n\
+            Show,2010,series,fr,,,\n\
+            Show,2010,,,1,1,Pilot\n\
+            Show,2010,,,1,2,\n\
+            Show,2010,,,2,1,Return\n\
+            Show,2020,series,,1,1,Remake\n");
+        let entries = load_csv(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        let show = &entries[0];
+        assert_eq!((show.kind.as_str(), show.original_language.as_deref()), ("series", Some("fr")));
+        assert_eq!(show.episodes.len(), 3);
+        assert_eq!(show.episode_title(1, 1), Some("Pilot"));
+        assert_eq!(show.episode_title(1, 2), None);
+        assert_eq!((show.season_size(1), show.season_size(3)), (Some(2), None));
+        assert_eq!(entries[1].episode_title(1, 1), Some("Remake"));
+    }
+}
@@ -7,6 +7,7 @@ use dialoguer::{Input, Confirm, theme::ColorfulTheme};
 use console::style;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
 pub struct Config {
     pub root_folder: PathBuf,
     pub output_root: PathBuf,
@@ -17,7 +18,92 @@ pub struct Config {
     pub ext_video: Vec<String>,
     pub ext_sub: Vec<String>,
     pub ext_audio: Vec<String>,
+    /// Extensions of chapter files; the file name must also end in `chapters` (e.g. `Movie.chapters.xml`).
+    pub ext_chapters: Vec<String>,
+    pub ext_fonts: Vec<String>,
+    /// Extensions of cover images (`poster.jpg`, `<stem>-poster.png`, ...).
+    pub ext_images: Vec<String>,
     pub concurrent_jobs: usize,
+    /// Subfolders searched for assets, case-insensitive. Nested paths like `Extras/Subs` are allowed.
+    pub asset_folders: Vec<String>,
+    /// Subfolders searched for fonts used by ASS/SSA subtitles, case-insensitive.
+    pub font_folders: Vec<String>,
+    /// Also search asset folders next to the video's folder (e.g. `Movie/Video/` + `Movie/Subs/`).
+    pub search_sibling_folders: bool,
+    /// How many parent folders up to look for assets shared by several videos.
+    pub shared_asset_depth: usize,
+    /// Name of the per-folder file with manual sync offsets (`"file.ac3" = 120`).
+    pub sync_sidecar_name: String,
+    /// Generate chapters at this interval (e.g. `5m`) for files without any. Empty disables it.
+    pub chapter_interval: String,
+    /// Check `[ABCD1234]` CRC32 tags in file names against the video before merging.
+    pub verify_crc32: bool,
+    /// Also copy cover art next to the output as `poster.jpg` / `<name>-poster.jpg`.
+    pub copy_cover_art: bool,
+    /// Write TITLE, DATE_RELEASED, PART_NUMBER and TOTAL_PARTS as Matroska global tags.
+    pub write_global_tags: bool,
+    /// Output naming: "plex", "jellyfin", "kodi", or "custom" to use the templates below.
+    pub naming_preset: String,
+    /// Placeholders: {title} {year} {season:02} {episode:02} {episode_title} {edition} {edition_tag}
+    /// {ext}, plus the release tags {resolution} {source} {codec} {audio} {hdr} {group}.
+    /// Folder templates may contain `/` for nested folders.
+    pub movie_folder_template: String,
+    pub movie_file_template: String,
+    pub series_folder_template: String,
+    pub episode_file_template: String,
+    /// How `{edition_tag}` is written: `{{edition-{edition}}}` (Plex), `- {edition}`, `[{edition}]`.
+    pub edition_template: String,
+    /// Templates for date-based episodes of daily shows; {air_date} is `YYYY-MM-DD`.
+    pub daily_folder_template: String,
+    pub daily_file_template: String,
+    /// How many parent folders are read for the show title and season (`Show/Season 2/E05.mkv`).
+    pub path_parse_depth: usize,
+    /// Add a note per file saying which file or folder supplied the title, season and episode.
+    pub explain_parsing: bool,
+    /// Folder for series specials (Season 00) inside the show folder. Empty keeps `Season 00`.
+    pub specials_folder: String,
+    /// Words whose spelling in titles is kept exactly as written here (e.g. "iCarly", "MythBusters").
+    pub title_case_exceptions: Vec<String>,
+    /// Episodes per season for anime with absolute numbering, e.g. `"Show Name" = [24, 24, 12]`.
+    /// Shows not listed keep their absolute number as `S01E137`.
+    pub anime_season_lengths: HashMap<String, Vec<u32>>,
+    /// File mapping alternative show and movie names to one canonical title, year and category.
+    pub aliases_file: PathBuf,
+    /// Before processing, ask for a canonical name for every title the alias file does not cover.
+    pub alias_prompt: bool,
+    /// Local metadata dumps (`.json` or `.csv`) with titles, years, original languages and episode titles.
+    pub metadata_catalogs: Vec<PathBuf>,
+    /// How similar (0-1) a parsed title must be to a catalog title to count as a match.
+    pub catalog_match_threshold: f64,
+    /// Rules sending content to other libraries than `Movies` and `TV Shows`; the first match wins.
+    pub routes: Vec<RouteRule>,
+}
+
+/// A `[[routes]]` entry. Content goes to the rule's library when every condition that is set
+/// matches; a rule without conditions catches everything.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct RouteRule {
+    /// Library name, also the folder under `output_root` unless `root` is set
+    pub name: String,
+    pub root: Option<PathBuf>,
+    /// Glob on the source path relative to `root_folder`, e.g. `**/Anime/**`
+    pub path_glob: String,
+    /// Regex on the parsed title, case-insensitive
+    pub title: String,
+    /// Original language from the metadata catalog, a language tag in the name or an audio track language
+    pub language: String,
+    /// Category given by the alias file
+    pub category: String,
+    /// Preset for this library; empty uses the main naming settings
+    pub naming_preset: String,
+    pub movie_folder_template: Option<String>,
+    pub movie_file_template: Option<String>,
+    pub series_folder_template: Option<String>,
+    pub episode_file_template: Option<String>,
+    pub daily_folder_template: Option<String>,
+    pub daily_file_template: Option<String>,
+    pub edition_template: Option<String>,
 }
 
 impl Default for Config {
@@ -30,9 +116,42 @@ impl Default for Config {
             delete_originals: false,
             default_sub_lang: "fin".to_string(),
             ext_video: vec![".mp4".into(), ".mkv".into(), ".avi".into(), ".mov".into()],
-            ext_sub: vec![".srt".into(), ".ass".into(), ".ssa".into(), ".vtt".into()],
-            ext_audio: vec![".aac".into(), ".mp3".into(), ".m4a".into(), ".flac".into(), ".wav".into()],
+            ext_sub: vec![".srt".into(), ".ass".into(), ".ssa".into(), ".vtt".into(), ".idx".into(), ".sub".into(), ".sup".into()],
+            ext_audio: vec![
+                ".aac".into(), ".mp3".into(), ".m4a".into(), ".flac".into(), ".wav".into(),
+                ".ac3".into(), ".eac3".into(), ".dts".into(), ".thd".into(), ".opus".into(), ".ogg".into(), ".mka".into(),
+            ],
+            ext_chapters: vec![".xml".into(), ".txt".into()],
+            ext_fonts: vec![".ttf".into(), ".otf".into(), ".ttc".into()],
+            ext_images: vec![".jpg".into(), ".jpeg".into(), ".png".into()],
             concurrent_jobs: 2,
+            asset_folders: vec!["Subs".into(), "Subtitles".into(), "Audio".into(), "Dubs".into(), "Extras/Subs".into()],
+            font_folders: vec!["Fonts".into(), "Attachments".into()],
+            search_sibling_folders: false,
+            shared_asset_depth: 0,
+            sync_sidecar_name: "sync.toml".into(),
+            chapter_interval: String::new(),
+            verify_crc32: false,
+            copy_cover_art: false,
+            write_global_tags: false,
+            naming_preset: "plex".into(),
+            movie_folder_template: "{title} ({year}) {edition_tag}".into(),
+            movie_file_template: "{title} ({year}) {edition_tag}.{ext}".into(),
+            series_folder_template: "{title} ({year})/Season {season:02}".into(),
+            edition_template: "{{edition-{edition}}}".into(),
+            episode_file_template: "{title} - S{season:02}E{episode:02} - {episode_title}.{ext}".into(),
+            daily_folder_template: "{title}/Season {year}".into(),
+            daily_file_template: "{title} - {air_date} - {episode_title}.{ext}".into(),
+            path_parse_depth: 2,
+            explain_parsing: false,
+            specials_folder: "Specials".into(),
+            title_case_exceptions: vec!["iCarly".into(), "MythBusters".into(), "WandaVision".into()],
+            anime_season_lengths: HashMap::new(),
+            aliases_file: PathBuf::from("aliases.toml"),
+            alias_prompt: false,
+            metadata_catalogs: Vec::new(),
+            catalog_match_threshold: 0.9,
+            routes: Vec::new(),
         }
     }
 }
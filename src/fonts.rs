@@ -0,0 +1,132 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use regex::Regex;
+use once_cell::sync::Lazy;
+
+/// `\fnFont Name` override tags inside ASS dialogue lines.
+static FN_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\\fn([^\\}]+)").unwrap());
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    data.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    data.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Reads the family, full and typographic family names of the font starting at `base`.
+fn names_from_font(data: &[u8], base: usize, names: &mut BTreeSet<String>) -> Option<()> {
+    let num_tables = read_u16(data, base + 4)? as usize;
+
+    let name_table = (0..num_tables)
+        .map(|i| base + 12 + i * 16)
+        .find(|&rec| data.get(rec..rec + 4) == Some(b"name"))
+        .and_then(|rec| read_u32(data, rec + 8))? as usize;
+
+    let count = read_u16(data, name_table + 2)? as usize;
+    let strings = name_table + read_u16(data, name_table + 4)? as usize;
+
+    for i in 0..count {
+        let rec = name_table + 6 + i * 12;
+        let platform = read_u16(data, rec)?;
+        let name_id = read_u16(data, rec + 6)?;
+        let length = read_u16(data, rec + 8)? as usize;
+        let offset = read_u16(data, rec + 10)? as usize;
+
+        // 1 = family, 4 = full name, 16 = typographic family
+        if ![1, 4, 16].contains(&name_id) {
+            continue;
+        }
+        let Some(raw) = data.get(strings + offset..strings + offset + length) else { continue };
+
+        let name = match platform {
+            // Unicode and Windows platforms store UTF-16BE
+            0 | 3 => {
+                let units: Vec<u16> = raw.chunks_exact(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+                String::from_utf16_lossy(&units)
+            }
+            _ => raw.iter().map(|&b| b as char).collect(),
+        };
+
+        let name = name.trim().to_lowercase();
+        if !name.is_empty() {
+            names.insert(name);
+        }
+    }
+
+    Some(())
+}
+
+/// Returns the lowercase names a TTF/OTF/TTC font can be referenced by.
+pub fn font_names(path: &Path) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let Ok(data) = fs::read(path) else { return names };
+
+    if data.starts_with(b"ttcf") {
+        let num_fonts = read_u32(&data, 8).unwrap_or(0) as usize;
+        for i in 0..num_fonts {
+            if let Some(offset) = read_u32(&data, 12 + i * 4) {
+                names_from_font(&data, offset as usize, &mut names);
+            }
+        }
+    } else {
+        names_from_font(&data, 0, &mut names);
+    }
+
+    names
+}
+
+/// Collects the lowercase font names used by an ASS/SSA file, from its styles and `\fn` tags.
+pub fn referenced_fonts(path: &Path) -> BTreeSet<String> {
+    let mut fonts = BTreeSet::new();
+    let Ok(bytes) = fs::read(path) else { return fonts };
+    let content = String::from_utf8_lossy(&bytes);
+
+    let mut in_styles = false;
+    let mut fontname_idx = 1;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_styles = line.eq_ignore_ascii_case("[V4+ Styles]") || line.eq_ignore_ascii_case("[V4 Styles]");
+            continue;
+        }
+
+        if in_styles {
+            if let Some(format) = line.strip_prefix("Format:") {
+                fontname_idx = format.split(',')
+                    .position(|f| f.trim().eq_ignore_ascii_case("Fontname"))
+                    .unwrap_or(1);
+            } else if let Some(style) = line.strip_prefix("Style:") {
+                if let Some(name) = style.split(',').nth(fontname_idx) {
+                    insert_font(&mut fonts, name);
+                }
+            }
+        } else if line.starts_with("Dialogue:") {
+            for caps in FN_TAG_RE.captures_iter(line) {
+                insert_font(&mut fonts, &caps[1]);
+            }
+        }
+    }
+
+    fonts
+}
+
+fn insert_font(fonts: &mut BTreeSet<String>, name: &str) {
+    // A leading '@' selects the vertical variant of the same font
+    let name = name.trim().trim_start_matches('@').to_lowercase();
+    if !name.is_empty() {
+        fonts.insert(name);
+    }
+}
+
+/// The MIME type Matroska players expect for a font attachment.
+pub fn font_mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("otf") => "font/otf",
+        Some("ttc") => "font/collection",
+        _ => "font/ttf",
+    }
+}
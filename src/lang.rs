@@ -1,21 +1,59 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use whatlang::detect;
 use crate::config::LANG_DATA;
 
+/// Matches the `id: en, index: 0` stream lines of a VobSub `.idx` file.
+static IDX_ID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^id:\s*([A-Za-z-]*)\s*,\s*index:\s*\d+").unwrap());
+
+/// Matches release-style track names such as `2_English` or `14_Finnish_SDH`.
+static TRACK_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d+_([A-Za-z]+)").unwrap());
+
+#[derive(Debug, Clone)]
 pub struct LangDetectResult {
     pub iso: String,
     pub name: String,
 }
 
-pub fn detect_subtitle_language(path: &Path) -> LangDetectResult {
+/// Resolves a language name or ISO 639-1/639-3 code (case-insensitive).
+pub fn lookup_language(token: &str) -> Option<LangDetectResult> {
+    let token = token.to_lowercase();
+    LANG_DATA.iter()
+        .find(|(iso1, (iso3, name))| **iso1 == token || **iso3 == token || name.to_lowercase() == token)
+        .map(|(_, (iso3, name))| LangDetectResult {
+            iso: iso3.to_string(),
+            name: name.to_string(),
+        })
+}
+
+/// Parses the language from `N_Language` style file stems.
+pub fn language_from_track_name(stem: &str) -> Option<LangDetectResult> {
+    let caps = TRACK_NAME_RE.captures(stem)?;
+    lookup_language(&caps[1])
+}
+
+/// Infers the language from the folder containing the file (e.g. `Subs/Finnish/`).
+pub fn language_from_folder(path: &Path) -> Option<LangDetectResult> {
+    let folder = path.parent()?.file_name()?.to_str()?;
+    lookup_language(folder)
+}
+
+/// Whether a file stem only names a track (`2_English`, `English`, `eng`) and carries
+/// nothing that identifies which video it belongs to.
+pub fn is_track_only_name(stem: &str) -> bool {
+    TRACK_NAME_RE.is_match(stem) || lookup_language(stem).is_some()
+}
+
+/// Looks for ISO language tags in the file name (e.g. `.fin.srt`, `_en.ac3`).
+fn language_from_filename_tags(path: &Path) -> Option<LangDetectResult> {
     let filename = path.file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("")
         .to_lowercase();
 
-    // Priority 1: Check filename tags
     for (iso1, (iso3, name)) in LANG_DATA.iter() {
         let patterns = [
             format!(".{}.", iso1),
@@ -26,16 +64,59 @@ pub fn detect_subtitle_language(path: &Path) -> LangDetectResult {
 
         for pat in patterns.iter() {
             if filename.contains(pat) {
-                return LangDetectResult {
+                return Some(LangDetectResult {
                     iso: iso3.to_string(),
                     name: name.to_string(),
-                };
+                });
             }
         }
     }
 
-    // Priority 2: Content analysis
-    if let Ok(file) = File::open(path) {
+    None
+}
+
+/// Determines the language of an external audio file from its name and folder.
+pub fn detect_audio_language(path: &Path) -> LangDetectResult {
+    if let Some(lang) = language_from_filename_tags(path) {
+        return lang;
+    }
+    if let Some(lang) = path.file_stem().and_then(|s| s.to_str()).and_then(language_from_track_name) {
+        return lang;
+    }
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+    if name.contains("fin") || name.contains("suomi") {
+        return LangDetectResult { iso: "fin".to_string(), name: "Finnish".to_string() };
+    }
+
+    language_from_folder(path).unwrap_or(LangDetectResult {
+        iso: "eng".to_string(),
+        name: "English".to_string(), // Default to English for now
+    })
+}
+
+pub fn detect_subtitle_language(path: &Path) -> LangDetectResult {
+    // Priority 1: Check filename tags
+    if let Some(lang) = language_from_filename_tags(path) {
+        return lang;
+    }
+
+    // Priority 2: Release-style track names (e.g. `2_English.srt`)
+    if let Some(lang) = path.file_stem().and_then(|s| s.to_str()).and_then(language_from_track_name) {
+        return lang;
+    }
+
+    // Priority 3: Language folders (e.g. `Subs/Finnish/`)
+    if let Some(lang) = language_from_folder(path) {
+        return lang;
+    }
+
+    // Priority 4: Content analysis (text formats only; VobSub and PGS are images)
+    let is_bitmap = path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["idx", "sub", "sup"].contains(&e.to_ascii_lowercase().as_str()));
+
+    if let Some(file) = File::open(path).ok().filter(|_| !is_bitmap) {
         let reader = BufReader::new(file);
         let mut sample_text = String::new();
         let mut total_chars = 0;
@@ -82,3 +163,21 @@ pub fn detect_subtitle_language(path: &Path) -> LangDetectResult {
         name: "Undefined".to_string(),
     }
 }
+
+/// Reads the language of every stream declared in a VobSub `.idx`, in track order.
+/// Streams with an unknown language code are returned as `und`.
+pub fn read_idx_languages(path: &Path) -> Vec<LangDetectResult> {
+    let Ok(file) = File::open(path) else { return Vec::new() };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let caps = IDX_ID_RE.captures(line.trim())?;
+            Some(lookup_language(&caps[1]).unwrap_or(LangDetectResult {
+                iso: "und".to_string(),
+                name: "Undefined".to_string(),
+            }))
+        })
+        .collect()
+}
@@ -1,7 +1,19 @@
+mod aliases;
+mod assignment;
+mod casing;
+mod catalog;
 mod config;
+mod fonts;
 mod lang;
+mod naming;
+mod probe;
 mod processor;
+mod release;
+mod routing;
 mod scanner;
+mod subtitles;
+mod sync;
+mod tags;
 
 use std::path::PathBuf;
 use std::time::Instant;
@@ -10,8 +22,14 @@ use walkdir::WalkDir;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle, HumanDuration};
 use console::{style, Emoji, Term};
 use rayon::prelude::*;
+use crate::aliases::{prompt_aliases, Aliases};
+use crate::assignment::build_asset_plan;
+use crate::catalog::Catalog;
 use crate::config::load_config_interactive;
+use crate::naming::NamingTemplates;
 use crate::processor::{Processor, ProcessStatus};
+use crate::routing::compile_routes;
+use crate::scanner::{group_parts, is_asset, is_cover_file, is_font_file, AssetIndex};
 
 // --- THEME & CONSTANTS ---
 static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "* ");
@@ -22,6 +40,15 @@ static SUCCESS: Emoji<'_, '_> = Emoji("✅ ", "+");
 static SKIPPED: Emoji<'_, '_> = Emoji("⏭️  ", "~");
 static FAILED:  Emoji<'_, '_> = Emoji("❌ ", "x");
 static TRASH:   Emoji<'_, '_> = Emoji("🗑️  ", "");
+static WARN:    Emoji<'_, '_> = Emoji("⚠️  ", "!");
+static CORRUPT: Emoji<'_, '_> = Emoji("💔 ", "#");
+
+/// Maximum number of entries listed per report panel before collapsing the rest
+const REPORT_LIMIT: usize = 15;
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
 
 /// Draws a stylish box with a title and content lines
 fn draw_panel(title: &str, content: &[String], color_func: fn(&str) -> console::StyledObject<&str>) {
@@ -57,6 +84,14 @@ fn draw_panel(title: &str, content: &[String], color_func: fn(&str) -> console::
     println!("{}", color_func(&format!("╰{}╯", horiz)));
 }
 
+/// Loads the alias file, reporting a broken one instead of stopping the run.
+fn load_aliases(path: &std::path::Path) -> Aliases {
+    Aliases::load(path).unwrap_or_else(|e| {
+        println!("{} {} {:#}", WARN, style("Aliases ignored:").yellow().bold(), style(e).yellow());
+        Aliases::default()
+    })
+}
+
 fn main() {
     // Prevent immediate close on panic
     std::panic::set_hook(Box::new(|info| {
@@ -97,31 +132,66 @@ fn main() {
         return;
     }
 
+    let naming = match NamingTemplates::from_config(&config) {
+        Ok(naming) => naming,
+        Err(e) => {
+            println!("\n{} {} {:#}", FAILED, style("Invalid naming preset:").red().bold(), style(e).red());
+            return;
+        }
+    };
+
+    let routes = match compile_routes(&config) {
+        Ok(routes) => routes,
+        Err(e) => {
+            println!("\n{} {} {:#}", FAILED, style("Invalid routing rule:").red().bold(), style(e).red());
+            return;
+        }
+    };
+
     // 2. Scan
     println!("\n{} {}", style("SCANNING LIBRARY...").bold(), style("Please wait").dim());
     let start_scan = Instant::now();
 
     let video_extensions = &config.ext_video;
-    let video_files: Vec<PathBuf> = WalkDir::new(&config.root_folder)
+    let is_video = |path: &std::path::Path| {
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            let fmt = format!(".{}", ext.to_ascii_lowercase());
+            video_extensions.contains(&fmt)
+        } else {
+            false
+        }
+    };
+
+    let scanned_files: Vec<PathBuf> = WalkDir::new(&config.root_folder)
         .into_iter()
         .par_bridge()
         .filter_map(|e| e.ok())
         .filter(|e| e.path().is_file())
         .filter(|e| {
-            if let Some(ext) = e.path().extension().and_then(|e| e.to_str()) {
-                let fmt = format!(".{}", ext.to_ascii_lowercase());
-                video_extensions.contains(&fmt)
-            } else {
-                false
-            }
+            let path = e.path();
+            is_video(path) || is_asset(path, &config) || is_font_file(path, &config) || is_cover_file(path, &config)
         })
         .map(|e| e.path().to_path_buf())
         .collect();
 
+    let (mut video_files, asset_files): (Vec<PathBuf>, Vec<PathBuf>) = scanned_files
+        .into_iter()
+        .partition(|p| is_video(p));
+    video_files.sort();
+
+    // One lookup index per directory, shared by all workers
+    let asset_index = AssetIndex::build(&asset_files, &config);
+
+    // Assign every asset to a single video up front so nothing is muxed (or deleted) twice
+    let asset_plan = build_asset_plan(&video_files, &asset_index, &config);
+
+    // CD1/CD2 rips of one movie become a single job
+    let jobs = group_parts(&video_files);
+
     let scan_time = start_scan.elapsed();
 
     if video_files.is_empty() {
-        println!("\n{} {}", style("NO FILES FOUND").yellow().bold(), "Check your source directory.");
+        println!("\n{} Check your source directory.", style("NO FILES FOUND").yellow().bold());
         println!("{}", style("Press Enter to exit...").white().dim());
         let _ = std::io::stdin().read_line(&mut String::new());
         return;
@@ -131,6 +201,62 @@ fn main() {
     let scan_msg = format!("Found {} files in {}", video_files.len(), HumanDuration(scan_time));
     println!("{} {}\n", style("SCAN COMPLETE").green().bold(), style(scan_msg).dim());
 
+    if !asset_plan.conflicts.is_empty() {
+        let mut lines: Vec<String> = asset_plan.conflicts.iter()
+            .take(REPORT_LIMIT)
+            .map(|c| format!("{} {} -> {} (score {}, {} other)",
+                WARN, file_name(&c.asset), style(file_name(&c.winner)).cyan(), c.score, c.losers.len()))
+            .collect();
+        if asset_plan.conflicts.len() > REPORT_LIMIT {
+            lines.push(style(format!("... and {} more", asset_plan.conflicts.len() - REPORT_LIMIT)).dim().to_string());
+        }
+        draw_panel("SHARED ASSETS RESOLVED", &lines, |s| style(s).yellow().bold());
+        println!();
+    }
+
+    if !asset_plan.orphans.is_empty() {
+        let mut lines: Vec<String> = asset_plan.orphans.iter()
+            .take(REPORT_LIMIT)
+            .map(|p| format!("{} {}", WARN, p.strip_prefix(&config.root_folder).unwrap_or(p).display()))
+            .collect();
+        if asset_plan.orphans.len() > REPORT_LIMIT {
+            lines.push(style(format!("... and {} more", asset_plan.orphans.len() - REPORT_LIMIT)).dim().to_string());
+        }
+        draw_panel("UNMATCHED ASSETS", &lines, |s| style(s).yellow().bold());
+        println!();
+    }
+
+    let mut processor = Processor::new((*config).clone(), naming);
+    processor.set_aliases(load_aliases(&config.aliases_file));
+    processor.set_routes(routes);
+    if !config.metadata_catalogs.is_empty() {
+        match Catalog::load(&config.metadata_catalogs, config.catalog_match_threshold) {
+            Ok(catalog) => {
+                println!("{} Metadata catalog: {} titles", GEAR, catalog.len());
+                processor.set_catalog(catalog);
+            }
+            Err(e) => println!("{} {} {:#}", WARN, style("Catalog ignored:").yellow().bold(), style(e).yellow()),
+        }
+    }
+    processor.register_batch(&jobs);
+
+    if config.alias_prompt {
+        let firsts: Vec<PathBuf> = jobs.iter().map(|parts| parts[0].clone()).collect();
+        let titles = processor.unaliased_titles(&firsts);
+        if !titles.is_empty() {
+            match prompt_aliases(&config.aliases_file, &titles) {
+                Ok(0) => {}
+                Ok(added) => {
+                    println!("{} Added {} alias(es) to {}", SPARKLE, added, config.aliases_file.display());
+                    processor.set_aliases(load_aliases(&config.aliases_file));
+                    processor.register_batch(&jobs);
+                }
+                Err(e) => println!("{} {}", WARN, style(format!("Alias prompt failed: {}", e)).yellow()),
+            }
+            println!();
+        }
+    }
+
     if !config.dry_run {
         println!("{}", style("Processing will start shortly...").dim());
         std::thread::sleep(std::time::Duration::from_millis(1500));
@@ -157,9 +283,8 @@ fn main() {
         active_bars.lock().unwrap().push(job_pb);
     }
 
-    // Stats (Success, Skipped, Failed)
-    let stats = Arc::new(Mutex::new((0, 0, 0)));
-    let processor = Processor::new((*config).clone());
+    // Stats (Success, Skipped, Failed, Corrupt)
+    let stats = Arc::new(Mutex::new((0, 0, 0, 0)));
 
     let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(config.concurrent_jobs)
@@ -167,13 +292,14 @@ fn main() {
         .unwrap();
 
     pool.install(|| {
-        video_files.par_iter().enumerate().for_each(|(idx, video)| {
+        jobs.par_iter().enumerate().for_each(|(idx, parts)| {
             let worker_id = idx % config.concurrent_jobs;
             let job_pb = {
                 let bars = active_bars.lock().unwrap();
                 bars[worker_id].clone()
             };
 
+            let video = &parts[0];
             let name = video.file_name().unwrap_or_default().to_string_lossy();
             let display_name = if name.chars().count() > 35 { 
                 format!("{}...", name.chars().take(32).collect::<String>()) 
@@ -184,10 +310,11 @@ fn main() {
             job_pb.set_style(ProgressStyle::with_template("  {spinner:.yellow} {msg}").unwrap());
             job_pb.set_message(format!("Processing: {}", style(&display_name).cyan()));
 
-            let result = processor.process_file(video);
+            let part_assets: Vec<_> = parts.iter().map(|part| asset_plan.assets_for(part)).collect();
+            let result = processor.process_file(parts, &part_assets);
             
             match result {
-                ProcessStatus::Success { subs, audios } => {
+                ProcessStatus::Success { subs, audios, notes, warnings } => {
                     stats.lock().unwrap().0 += 1;
                     let info = if subs > 0 || audios > 0 {
                         let sub_info = if subs > 0 { format!("{} subs", subs) } else { String::new() };
@@ -198,6 +325,12 @@ fn main() {
                         "Merged (no extra assets)".to_string()
                     };
                     let _ = multiprogress.println(format!("{} {} -> {}", SUCCESS, display_name, style(info).green()));
+                    for note in notes {
+                        let _ = multiprogress.println(format!("    {}", style(note).dim()));
+                    }
+                    for warning in warnings {
+                        let _ = multiprogress.println(format!("    {} {}", WARN, style(warning).yellow()));
+                    }
                 },
                 ProcessStatus::Skipped => {
                     stats.lock().unwrap().1 += 1;
@@ -207,11 +340,16 @@ fn main() {
                     stats.lock().unwrap().2 += 1;
                     let _ = multiprogress.println(format!("{} {} -> {}", FAILED, display_name, style(e).red()));
                 }
+                ProcessStatus::Corrupt { expected, actual } => {
+                    stats.lock().unwrap().3 += 1;
+                    let msg = format!("Corrupt: CRC32 is {}, file name says {}", actual, expected);
+                    let _ = multiprogress.println(format!("{} {} -> {}", CORRUPT, display_name, style(msg).red().bold()));
+                }
             }
 
             job_pb.set_style(ProgressStyle::with_template("  {spinner:.dim} {msg}").unwrap());
             job_pb.set_message(style(format!("Worker {} idle", worker_id + 1)).dim().to_string());
-            pb.inc(1);
+            pb.inc(parts.len() as u64);
         });
     });
 
@@ -223,8 +361,8 @@ fn main() {
 
     // 4. Summary Panel
     let final_stats = stats.lock().unwrap();
-    let (success, skipped, failed) = *final_stats;
-    let total = success + skipped + failed;
+    let (success, skipped, failed, corrupt) = *final_stats;
+    let total = success + skipped + failed + corrupt;
     let deleted = if config.delete_originals { success } else { 0 };
 
     let success_pct = if total > 0 { (success as f32 / total as f32) * 100.0 } else { 0.0 };
@@ -234,6 +372,7 @@ fn main() {
         format!("{} {:<18} {} ({:.1}%)", SUCCESS, "Successfully Merged:", style(success).green().bold(), success_pct),
         format!("{} {:<18} {}", SKIPPED, "Skipped (Exists):", style(skipped).yellow()),
         format!("{} {:<18} {}", FAILED, "Failures:", style(failed).red()),
+        format!("{} {:<18} {}", CORRUPT, "Corrupt (CRC32):", if corrupt > 0 { style(corrupt.to_string()).red().bold() } else { style("0".to_string()).dim() }),
         format!("{} {:<18} {}", TRASH, "Originals Deleted:", if deleted > 0 { style(deleted.to_string()).red().bold() } else { style("0".to_string()).dim() }),
         style("━".repeat(20)).dim().to_string(),
         format!("{} Total Time: {}", SPARKLE, style(HumanDuration(start_scan.elapsed())).cyan().bold()),
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use anyhow::{bail, Result};
+use regex::Regex;
+use once_cell::sync::Lazy;
+use crate::config::Config;
+
+static EMPTY_BRACKETS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\(\s*\)|\[\s*\]").unwrap());
+static DANGLING_DASH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+-(\s+-)+\s+").unwrap());
+static EPISODE_PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[Ee]?\{\s*episode\s*(?::[^}]*)?\}").unwrap());
+static SEPARATOR_BEFORE_EXT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\s\-]+(\.[A-Za-z0-9]+)$").unwrap());
+
+/// A value substituted into a naming template.
+#[derive(Debug, Clone)]
+pub enum TemplateValue {
+    Text(String),
+    Number(u32),
+    /// Episode numbers; a double episode renders as `01-E02`, so `E{episode:02}` gives `E01-E02`.
+    Episodes(Vec<u32>),
+}
+
+/// The templates that make up a library layout. Folder templates may contain `/`.
+#[derive(Debug, Clone)]
+pub struct NamingTemplates {
+    pub movie_folder: String,
+    pub movie_file: String,
+    pub series_folder: String,
+    pub episode_file: String,
+    /// Layout for date-based episodes of daily shows
+    pub daily_folder: String,
+    pub daily_file: String,
+    /// How an edition is written where `{edition_tag}` appears, e.g. `{{edition-{edition}}}`
+    pub edition: String,
+}
+
+impl NamingTemplates {
+    /// Built-in layouts following the Plex, Jellyfin and Kodi naming guides.
+    pub fn preset(name: &str) -> Option<Self> {
+        let (movie_folder, movie_file, series_folder, episode_file, daily_folder, daily_file, edition) = match name.to_lowercase().as_str() {
+            "plex" => (
+                "{title} ({year}) {edition_tag}",
+                "{title} ({year}) {edition_tag}.{ext}",
+                "{title} ({year})/Season {season:02}",
+                "{title} - S{season:02}E{episode:02} - {episode_title}.{ext}",
+                "{title}/Season {year}",
+                "{title} - {air_date} - {episode_title}.{ext}",
+                "{{edition-{edition}}}",
+            ),
+            "jellyfin" => (
+                "{title} ({year})",
+                "{title} ({year}) {edition_tag}.{ext}",
+                "{title} ({year})/Season {season:02}",
+                "{title} S{season:02}E{episode:02} {episode_title}.{ext}",
+                "{title}/Season {year}",
+                "{title} {air_date} {episode_title}.{ext}",
+                "- {edition}",
+            ),
+            "kodi" => (
+                "{title} ({year})",
+                "{title} ({year}).{ext}",
+                "{title} ({year})/Season {season}",
+                "{title} S{season:02}E{episode:02}.{ext}",
+                "{title}/Season {year}",
+                "{title} {air_date}.{ext}",
+                "",
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            movie_folder: movie_folder.to_string(),
+            movie_file: movie_file.to_string(),
+            series_folder: series_folder.to_string(),
+            episode_file: episode_file.to_string(),
+            daily_folder: daily_folder.to_string(),
+            daily_file: daily_file.to_string(),
+            edition: edition.to_string(),
+        })
+    }
+
+    /// The preset named in the config, or the config's own templates for `custom`.
+    /// Any other name is an error rather than a silent switch to the custom templates.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        if config.naming_preset.eq_ignore_ascii_case("custom") {
+            return Ok(Self {
+                movie_folder: config.movie_folder_template.clone(),
+                movie_file: config.movie_file_template.clone(),
+                series_folder: config.series_folder_template.clone(),
+                episode_file: config.episode_file_template.clone(),
+                daily_folder: config.daily_folder_template.clone(),
+                daily_file: config.daily_file_template.clone(),
+                edition: config.edition_template.clone(),
+            });
+        }
+        match Self::preset(&config.naming_preset) {
+            Some(preset) => Ok(preset),
+            None => bail!("unknown naming preset \"{}\" (expected plex, jellyfin, kodi or custom)", config.naming_preset),
+        }
+    }
+}
+
+/// An episode template for a special without a number: the episode placeholder is dropped and
+/// the episode title added if the template has none, so `{title} - S{season:02}E{episode:02} -
+/// {episode_title}.{ext}` names a special `Doctor Who - S00 - Christmas Special.mkv`.
+pub fn without_episode(template: &str) -> String {
+    let template = EPISODE_PLACEHOLDER_RE.replace_all(template, "").into_owned();
+    if template.contains("{episode_title") {
+        return template;
+    }
+    match template.strip_suffix(".{ext}") {
+        Some(base) => format!("{} - {{episode_title}}.{{ext}}", base),
+        None => format!("{} - {{episode_title}}", template),
+    }
+}
+
+/// Removes characters that are not allowed in file names on Windows.
+fn sanitize(value: &str) -> String {
+    value.chars()
+        .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'))
+        .collect()
+}
+
+/// Tidies a rendered name after empty placeholders: drops `()`/`[]`, repeated separators and
+/// trailing dashes or dots.
+fn tidy(name: &str) -> String {
+    let name = EMPTY_BRACKETS_RE.replace_all(name, "");
+    let name = DANGLING_DASH_RE.replace_all(&name, " - ");
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = SEPARATOR_BEFORE_EXT_RE.replace(&name, "$1");
+    name.trim_matches(|c: char| c == '-' || c == '.' || c.is_whitespace()).to_string()
+}
+
+/// Renders a template such as `{title} - S{season:02}E{episode:02}`.
+///
+/// `{name:0N}` zero-pads numbers to N digits. Unknown or missing placeholders render empty and
+/// the leftovers are tidied up. `{{` and `}}` produce literal braces. The result is split on
+/// `/` into path components, each tidied separately.
+pub fn render(template: &str, values: &HashMap<&str, TemplateValue>) -> Vec<String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); out.push('{'); }
+            '}' if chars.peek() == Some(&'}') => { chars.next(); out.push('}'); }
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (name, format) = spec.split_once(':').unwrap_or((&spec, ""));
+                let width = format.trim_start_matches('0').parse().unwrap_or(0);
+
+                match values.get(name.trim()) {
+                    Some(TemplateValue::Number(n)) => out.push_str(&format!("{:0width$}", n, width = width)),
+                    Some(TemplateValue::Episodes(list)) => {
+                        if let (Some(first), Some(last)) = (list.first(), list.last()) {
+                            out.push_str(&format!("{:0width$}", first, width = width));
+                            if last != first {
+                                out.push_str(&format!("-E{:0width$}", last, width = width));
+                            }
+                        }
+                    }
+                    Some(TemplateValue::Text(t)) => out.push_str(&sanitize(t)),
+                    None => {}
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out.split('/')
+        .map(tidy)
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&'static str, TemplateValue)]) -> HashMap<&'static str, TemplateValue> {
+        pairs.iter().cloned().collect()
+    }
+
+    fn text(value: &str) -> TemplateValue {
+        TemplateValue::Text(value.to_string())
+    }
+
+    #[test]
+    fn renders_episode_with_zero_padding() {
+        let values = values(&[
+            ("title", text("Show")),
+            ("season", TemplateValue::Number(1)),
+            ("episode", TemplateValue::Episodes(vec![5])),
+            ("episode_title", text("Pilot")),
+            ("ext", text("mkv")),
+        ]);
+        let plex = NamingTemplates::preset("plex").unwrap();
+        assert_eq!(render(&plex.episode_file, &values), vec!["Show - S01E05 - Pilot.mkv"]);
+        assert_eq!(render("{title}/Season {season:02}", &values), vec!["Show", "Season 01"]);
+    }
+
+    #[test]
+    fn renders_double_episode_as_range() {
+        let values = values(&[("season", TemplateValue::Number(2)), ("episode", TemplateValue::Episodes(vec![1, 2]))]);
+        assert_eq!(render("S{season:02}E{episode:02}", &values), vec!["S02E01-E02"]);
+    }
+
+    #[test]
+    fn missing_values_are_tidied_away() {
+        let values = values(&[("title", text("Show")), ("ext", text("mkv"))]);
+        assert_eq!(render("{title} ({year}) - {episode_title} - .{ext}", &values), vec!["Show.mkv"]);
+        assert_eq!(render("{title} [{edition_tag}]", &values), vec!["Show"]);
+    }
+
+    #[test]
+    fn double_braces_are_literal() {
+        let values = values(&[("edition", text("Director's Cut"))]);
+        let plex = NamingTemplates::preset("plex").unwrap();
+        assert_eq!(render(&plex.edition, &values), vec!["{edition-Director's Cut}"]);
+    }
+
+    #[test]
+    fn text_values_cannot_add_path_components() {
+        let values = values(&[("title", text("AC/DC: Live"))]);
+        assert_eq!(render("{title}", &values), vec!["ACDC Live"]);
+    }
+
+    #[test]
+    fn unknown_preset_is_an_error() {
+        let mut config = Config { naming_preset: "custom".into(), ..Config::default() };
+        assert_eq!(NamingTemplates::from_config(&config).unwrap().movie_file, config.movie_file_template);
+        config.naming_preset = "Jellyfin".into();
+        assert!(NamingTemplates::from_config(&config).is_ok());
+        config.naming_preset = "plexx".into();
+        assert!(NamingTemplates::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn unnumbered_specials_drop_the_episode() {
+        let plex = NamingTemplates::preset("plex").unwrap();
+        assert_eq!(without_episode(&plex.episode_file), "{title} - S{season:02} - {episode_title}.{ext}");
+        let kodi = NamingTemplates::preset("kodi").unwrap();
+        assert_eq!(without_episode(&kodi.episode_file), "{title} S{season:02} - {episode_title}.{ext}");
+    }
+}
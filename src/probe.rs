@@ -0,0 +1,123 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+/// The subset of `mkvmerge -J` output we care about.
+#[derive(Debug, Deserialize)]
+pub struct Identification {
+    pub container: ContainerInfo,
+    #[serde(default)]
+    pub tracks: Vec<TrackInfo>,
+    #[serde(default)]
+    pub chapters: Vec<ChapterInfo>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChapterInfo {
+    #[serde(default)]
+    pub num_entries: usize,
+}
+
+impl Identification {
+    pub fn has_chapters(&self) -> bool {
+        self.chapters.iter().any(|c| c.num_entries > 0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContainerInfo {
+    #[serde(default)]
+    pub recognized: bool,
+    #[serde(default)]
+    pub supported: bool,
+    #[serde(default)]
+    pub properties: ContainerProperties,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ContainerProperties {
+    /// Playing time in nanoseconds
+    pub duration: Option<u64>,
+}
+
+impl ContainerInfo {
+    /// Playing time in milliseconds, if mkvmerge could determine it.
+    pub fn duration_ms(&self) -> Option<i64> {
+        self.properties.duration.map(|ns| (ns / 1_000_000) as i64)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackInfo {
+    pub id: usize,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub codec: String,
+    #[serde(default)]
+    pub properties: TrackProperties,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TrackProperties {
+    pub language: Option<String>,
+    pub audio_channels: Option<u32>,
+}
+
+impl TrackInfo {
+    pub fn is_audio(&self) -> bool {
+        self.kind == "audio"
+    }
+
+    /// The track language if it is set to something other than `und`.
+    pub fn language(&self) -> Option<&str> {
+        self.properties.language.as_deref().filter(|l| *l != "und")
+    }
+
+    /// Human readable codec and channel layout, e.g. "AC-3 5.1".
+    pub fn codec_label(&self) -> String {
+        match self.properties.audio_channels {
+            Some(ch) => format!("{} {}", self.codec, channel_layout(ch)),
+            None => self.codec.clone(),
+        }
+    }
+}
+
+/// Maps a channel count to the usual layout name.
+fn channel_layout(channels: u32) -> String {
+    match channels {
+        1 => "1.0".to_string(),
+        2 => "2.0".to_string(),
+        3 => "2.1".to_string(),
+        6 => "5.1".to_string(),
+        7 => "6.1".to_string(),
+        8 => "7.1".to_string(),
+        n => format!("{}ch", n),
+    }
+}
+
+/// Runs `mkvmerge -J` on a file and fails if mkvmerge cannot read it.
+pub fn identify(mkvmerge: &Path, file: &Path) -> Result<Identification> {
+    let output = Command::new(mkvmerge)
+        .arg("-J")
+        .arg(file)
+        .output()
+        .context("could not run mkvmerge")?;
+
+    let info: Identification = serde_json::from_slice(&output.stdout)
+        .context("unreadable identification output")?;
+
+    if let Some(err) = info.errors.first() {
+        bail!("{}", err);
+    }
+    if !info.container.recognized {
+        bail!("unrecognized file format");
+    }
+    if !info.container.supported {
+        bail!("unsupported file format");
+    }
+
+    Ok(info)
+}
@@ -1,67 +1,592 @@
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::process::Command;
 use std::fs;
+use std::io::Read;
 use regex::Regex;
+use once_cell::unsync::OnceCell;
+use crate::aliases::{Aliases, Canonical};
+use crate::casing::title_case;
+use crate::catalog::Catalog;
 use crate::config::Config;
-use crate::scanner::find_matching_assets;
-use crate::lang::detect_subtitle_language;
+use crate::fonts::{font_mime_type, font_names, referenced_fonts};
+use crate::scanner::{split_part, CoverArt, FoundAssets};
+use crate::lang::{detect_audio_language, detect_subtitle_language, lookup_language, read_idx_languages, LangDetectResult};
+use crate::probe::{identify, Identification, TrackInfo};
+use crate::subtitles::{is_joinable, join_subtitles};
+use crate::sync::sync_offset;
+use crate::tags::global_tags_xml;
+use crate::naming::{render, without_episode, NamingTemplates, TemplateValue};
+use crate::release::{split_release, starts_with_tag, ReleaseTags};
+use crate::routing::{Route, RouteInput};
+
+/// Keeps temporary tag and subtitle file names unique across parallel workers
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+/// Longest episode range taken from a name; anything wider is a title word, not a multi-episode file.
+const MAX_EPISODE_SPAN: u32 = 10;
+
+/// `mkvmerge -J` output of a job's video, filled by whichever step needs it first.
+type VideoProbe = OnceCell<Result<Identification, String>>;
 
 pub struct Processor {
     config: Config,
     regex_series_standard: Regex,
     regex_series_x: Regex,
     regex_year: Regex,
-    regex_season_only: Regex,
+    /// `2024.03.15` air dates of daily shows
+    regex_date_ymd: Regex,
+    /// `15.03.2024` air dates of daily shows
+    regex_date_dmy: Regex,
+    /// Season folders: `Season 2`, `S02`, or season packs like `Show.S02` once release tags are gone
+    regex_season_folder: Regex,
+    /// File names that only carry the episode: `E05`, `Episode 5`, `05 - Title`
+    regex_episode_only: Regex,
+    /// Trailing year of a show title: `Doctor.Who.2005`, `Doctor Who (2005)`
+    regex_show_year: Regex,
+    /// `Special`, `Christmas Special` or `OVA` in a file name
+    regex_special: Regex,
+    /// Movie extras such as `Featurette`, `Behind the Scenes` or `Trailer`
+    regex_extra: Regex,
+    /// A further episode after the first: `E02`, `-E03`, `x02` or `-03`
+    regex_episode_more: Regex,
+    /// Leading `[Group]` of fansub releases
+    regex_fansub_group: Regex,
+    /// `[ABCD1234]` CRC32 checksum tag
+    regex_crc: Regex,
+    /// `Show Name - 137v2 - Title` absolute episode numbering
+    regex_absolute: Regex,
+    naming: NamingTemplates,
+    /// Font names per font file, shared by all workers since fonts serve many episodes
+    font_names: Mutex<HashMap<PathBuf, BTreeSet<String>>>,
+    /// Episodes per (show, season) in the current batch, used for TOTAL_PARTS
+    season_sizes: HashMap<(String, u32), u32>,
+    /// Videos that are one part of a multi-part movie in the current batch
+    part_files: HashSet<PathBuf>,
+    /// Canonical titles from the alias file
+    aliases: Aliases,
+    /// Offline metadata for canonical titles, years and episode titles
+    catalog: Catalog,
+    /// Library routing rules, in config order
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MediaInfo {
+    pub title: String,
+    pub is_series: bool,
+    pub year: Option<String>,
+    pub season: Option<u32>,
+    /// All episodes in the file, sorted; more than one for double episodes like `S01E01E02`
+    pub episodes: Vec<u32>,
+    pub episode_title: Option<String>,
+    /// Air date of daily shows as `YYYY-MM-DD`
+    pub air_date: Option<String>,
+    /// Absolute episode number of anime releases, before mapping to season/episode
+    pub absolute: Option<u32>,
+    /// Release version, e.g. 2 for `137v2`
+    pub version: Option<u32>,
+    /// CRC32 from a `[ABCD1234]` tag, uppercase
+    pub crc32: Option<String>,
+    /// Series special, filed under Season 00 or `specials_folder`
+    pub special: bool,
+    /// Movie extra: the extras folder (`Featurettes`, `Trailers`, ...) and the extra's own name
+    pub extra: Option<(&'static str, String)>,
+    /// Which path component supplied each field, for `explain_parsing`
+    pub sources: Vec<(&'static str, String)>,
+    /// Resolution, source, codecs, edition and group found in the file name
+    pub release: ReleaseTags,
+    /// Movie edition such as "Director's Cut" or "Extended"
+    pub edition: Option<String>,
+    /// The alias entry that supplied the canonical title
+    pub alias: Option<Canonical>,
+    /// ISO 639-2 code of the original audio language, from the metadata catalog
+    pub original_language: Option<String>,
+    /// Episodes the metadata catalog lists for this season
+    pub catalog_season_size: Option<u32>,
+}
+
+impl MediaInfo {
+    /// The first episode of the file.
+    pub fn episode(&self) -> Option<u32> {
+        self.episodes.first().copied()
+    }
+
+    /// Segment title shown by players: "Title (Year)" for movies, "Show – S01E02" or
+    /// "Show – S01E01-E02" for episodes.
+    pub fn segment_title(&self) -> String {
+        if let Some(date) = &self.air_date {
+            return format!("{} – {}", self.title, date);
+        }
+        match (self.is_series, self.season, self.episodes.as_slice(), &self.year) {
+            (true, Some(s), [e], _) => format!("{} – S{:02}E{:02}", self.title, s, e),
+            (true, Some(s), [first, .., last], _) => format!("{} – S{:02}E{:02}-E{:02}", self.title, s, first, last),
+            (false, _, _, Some(y)) => format!("{} ({})", self.title, y),
+            _ => self.title.clone(),
+        }
+    }
 }
 
-#[derive(Debug)]
-struct MediaInfo {
-    title: String,
-    season_folder: String, 
-    is_series: bool,
+/// Show title and season found in the folders above a video, with the folder they came from.
+#[derive(Default)]
+struct FolderHints {
+    title: Option<(String, String)>,
+    /// Year next to the show title, e.g. `Doctor Who (2005)`
     year: Option<String>,
+    season: Option<(u32, String)>,
 }
 
 pub enum ProcessStatus {
-    Success { subs: usize, audios: usize },
+    /// `notes` describe the merge plan, `warnings` anything that was left out of it.
+    Success { subs: usize, audios: usize, notes: Vec<String>, warnings: Vec<String> },
     Skipped,
     Failed(String),
+    /// The CRC32 tag in the file name does not match the file contents.
+    Corrupt { expected: String, actual: String },
+}
+
+/// CRC32 of a whole file, read in chunks so large videos are not loaded into memory.
+fn file_crc32(path: &Path) -> std::io::Result<u32> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize());
+        }
+        hasher.update(&buf[..n]);
+    }
 }
 
 impl Processor {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config, naming: NamingTemplates) -> Self {
         Self {
+            naming,
             config,
             regex_series_standard: Regex::new(r"(?i)^(.*?)[\. \-_]+s(\d+)[\. \-_]*e(\d+)").unwrap(),
             regex_series_x: Regex::new(r"(?i)^(.*?)[\. \-_]+(\d+)x(\d+)").unwrap(),
-            regex_year: Regex::new(r"(?i)^(.*?)[\. \-_]+(\d{4})").unwrap(),
-            regex_season_only: Regex::new(r"(?i)(?:season|s)[\. \-_]?(\d{1,2})").unwrap(),
+            regex_year: Regex::new(r"(?i)^(.*?)[\. \-_(\[]+(\d{4})").unwrap(),
+            regex_date_ymd: Regex::new(r"^(.*?)[\. \-_]+((?:19|20)\d{2})[\. \-_](\d{2})[\. \-_](\d{2})(?:[\. \-_]|$)").unwrap(),
+            regex_date_dmy: Regex::new(r"^(.*?)[\. \-_]+(\d{2})[\. \-_](\d{2})[\. \-_]((?:19|20)\d{2})(?:[\. \-_]|$)").unwrap(),
+            regex_season_folder: Regex::new(r"(?i)^(?:(.*?)[\. \-_]+)?(?:s|season|series)[\. \-_]?(\d{1,2})$").unwrap(),
+            regex_show_year: Regex::new(r"^(.+?)[\. \-_]+[(\[]?((?:19|20)\d{2})[)\]]?$").unwrap(),
+            regex_special: Regex::new(r"(?i)[\. _\-]((?:(?:christmas|holiday|halloween)[\. _])?special|ova|oad)(?:[\. _\-]*(\d{1,3}))?(?:[\. _\-]|$)").unwrap(),
+            regex_extra: Regex::new(r"(?i)(?:^|[\. _\-])(featurette|behind[\. _]the[\. _]scenes|making[\. _]of|deleted[\. _]scene|trailer|teaser|interview)s?(?:[\. _\-]|$)").unwrap(),
+            regex_episode_only: Regex::new(r"(?i)^(?:(?:e|ep|episode)[\. _\-]?(\d{1,3})|(\d{1,3})(?:$|[\. _]))(.*)$").unwrap(),
+            regex_episode_more: Regex::new(r"(?i)^(?:[\. _]*-?[\. _]*e|x|-)(\d{1,3})").unwrap(),
+            regex_fansub_group: Regex::new(r"^\s*\[([^\]]+)\][\s_]*").unwrap(),
+            regex_crc: Regex::new(r"[\[(]([0-9A-Fa-f]{8})[\])]").unwrap(),
+            regex_absolute: Regex::new(r"(?i)^(.*?)[ _]+-[ _]+(\d{1,4})(?:v(\d{1,2}))?(?:[ _]+-[ _]+(.*))?$").unwrap(),
+            font_names: Mutex::new(HashMap::new()),
+            season_sizes: HashMap::new(),
+            part_files: HashSet::new(),
+            aliases: Aliases::default(),
+            catalog: Catalog::default(),
+            routes: Vec::new(),
+        }
+    }
+
+    pub fn set_aliases(&mut self, aliases: Aliases) {
+        self.aliases = aliases;
+    }
+
+    pub fn set_catalog(&mut self, catalog: Catalog) {
+        self.catalog = catalog;
+    }
+
+    pub fn set_routes(&mut self, routes: Vec<Route>) {
+        self.routes = routes;
+    }
+
+    /// Distinct parsed titles (with year) of the batch that no alias covers, sorted.
+    pub fn unaliased_titles(&self, videos: &[PathBuf]) -> Vec<(String, Option<String>)> {
+        let titles: BTreeSet<_> = videos.iter()
+            .map(|video| self.parse_media_info(video))
+            .filter(|meta| meta.alias.is_none())
+            .map(|meta| (meta.title, if meta.air_date.is_some() { None } else { meta.year }))
+            .collect();
+        titles.into_iter().collect()
+    }
+
+    /// Records the multi-part movies and counts the distinct episodes per show and season
+    /// across the whole batch of jobs.
+    pub fn register_batch(&mut self, jobs: &[Vec<PathBuf>]) {
+        self.part_files = jobs.iter().filter(|parts| parts.len() > 1).flatten().cloned().collect();
+
+        let mut episodes: HashMap<(String, u32), BTreeSet<u32>> = HashMap::new();
+        for video in jobs.iter().filter_map(|parts| parts.first()) {
+            let meta = self.parse_media_info(video);
+            // Daily shows and unnumbered specials have no episodes to count
+            if let (true, Some(season), false) = (meta.is_series, meta.season, meta.episodes.is_empty()) {
+                episodes.entry((meta.title, season)).or_default().extend(meta.episodes);
+            }
         }
+        self.season_sizes = episodes.into_iter().map(|(k, v)| (k, v.len() as u32)).collect();
     }
 
     fn clean_title(&self, input: &str) -> String {
-        input.replace(['.', '_', '-'], " ")
-            .split_whitespace()
-            .filter(|w| !w.starts_with('(') && !w.ends_with(')')) // Ignore trailing (2024) etc if caught
-            .map(|w| {
-                let mut chars = w.chars();
-                match chars.next() {
-                    Some(c) => c.to_uppercase().collect::<String>() + chars.as_str().to_lowercase().as_str(),
-                    None => String::new(),
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(" ")
-            .trim()
-            .to_string()
+        title_case(input, &self.config.title_case_exceptions)
     }
 
     fn parse_media_info(&self, path: &Path) -> MediaInfo {
         let filename = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-        
+
+        // Fansub releases: `[Group] Show - 137 [1080p][ABCD1234]`
+        let group = self.regex_fansub_group.captures(filename).map(|c| c[1].trim().to_string());
+        let crc32 = self.regex_crc.captures_iter(filename).last().map(|c| c[1].to_uppercase());
+        let stripped = self.regex_fansub_group.replace(filename, "");
+        let stripped = self.regex_crc.replace_all(&stripped, "");
+
+        // Release tags are split off first so "1080p" is never mistaken for a year or title word
+        let (name, release) = split_release(&stripped);
+        let name = match split_part(&name) {
+            Some((base, _)) if self.part_files.contains(path) => base.to_string(),
+            _ => name,
+        };
+        let mut info = group.as_ref()
+            .and_then(|_| self.parse_absolute(&name))
+            .unwrap_or_else(|| self.parse_layout(path, &name));
+
+        info.edition = release.edition.clone();
+        info.release = release;
+        if info.release.group.is_none() {
+            info.release.group = group;
+        }
+        info.crc32 = crc32;
+
+        self.merge_folders(path, &name, &mut info);
+        self.classify_special(path, &name, &mut info);
+        self.apply_alias(&mut info);
+        self.apply_catalog(&mut info);
+        info
+    }
+
+    /// Fills in what the metadata catalog knows about the title: the canonical spelling (unless
+    /// an alias already chose one), a missing year, missing episode titles and the original language.
+    fn apply_catalog(&self, info: &mut MediaInfo) {
+        let year = if info.air_date.is_some() { None } else { info.year.as_deref().and_then(|y| y.parse().ok()) };
+        let Some(entry) = self.catalog.lookup(&info.title, year, info.is_series) else { return };
+        let source = format!("catalog \"{}\"", entry.title);
+
+        if info.alias.is_none() && info.title != entry.title {
+            info.title = entry.title.clone();
+            info.sources.retain(|(field, _)| *field != "title");
+            info.sources.push(("title", source.clone()));
+        }
+        if let (None, None, Some(year)) = (&info.year, &info.air_date, entry.year) {
+            info.year = Some(year.to_string());
+            info.sources.push(("year", source.clone()));
+        }
+        if let (None, Some(season)) = (&info.episode_title, info.season) {
+            let mut titles: Vec<&str> = info.episodes.iter().filter_map(|e| entry.episode_title(season, *e)).collect();
+            titles.dedup();
+            if !titles.is_empty() {
+                info.episode_title = Some(titles.join(" & "));
+                info.sources.push(("episode_title", source));
+            }
+        }
+        info.original_language = entry.original_language.as_deref().and_then(lookup_language).map(|l| l.iso);
+        info.catalog_season_size = info.season.and_then(|s| entry.season_size(s));
+    }
+
+    /// Replaces the parsed title with its canonical name from the alias file. The alias year
+    /// replaces the parsed one except for daily shows, whose year comes from the air date.
+    fn apply_alias(&self, info: &mut MediaInfo) {
+        let year = if info.air_date.is_some() { None } else { info.year.as_deref() };
+        let Some(canonical) = self.aliases.lookup(&info.title, year) else { return };
+
+        let source = format!("alias \"{}\"", canonical.title);
+        info.title = canonical.title.clone();
+        info.sources.retain(|(field, _)| *field != "title");
+        info.sources.push(("title", source.clone()));
+        if let (Some(year), None) = (canonical.year, &info.air_date) {
+            info.year = Some(year.to_string());
+            info.sources.retain(|(field, _)| *field != "year");
+            info.sources.push(("year", source));
+        }
+        info.alias = Some(canonical.clone());
+    }
+
+    /// Plex/Jellyfin extras folder for an extras keyword or folder name.
+    fn extra_folder(kind: &str) -> Option<&'static str> {
+        let kind = kind.trim().to_lowercase().replace(['.', '_'], " ");
+        match kind.trim_end_matches('s') {
+            "featurette" => Some("Featurettes"),
+            "behind the scene" | "making of" => Some("Behind The Scenes"),
+            "deleted scene" => Some("Deleted Scenes"),
+            "trailer" | "teaser" => Some("Trailers"),
+            "interview" => Some("Interviews"),
+            "short" => Some("Shorts"),
+            "extra" => Some("Other"),
+            _ => None,
+        }
+    }
+
+    /// Marks series specials (`S00E03`, `Show.Christmas.Special`, `Show - OVA 2`) and movie
+    /// extras, found either by a keyword in the name or by an extras folder like `Featurettes/`.
+    /// Extras inside such a folder take the movie from the nearest folder above it.
+    fn classify_special(&self, path: &Path, name: &str, info: &mut MediaInfo) {
+        if info.is_series {
+            info.special = info.season == Some(0);
+            return;
+        }
+
+        // Like extras, the keyword must end the name or follow the title and year:
+        // `Doctor.Who.2005.Christmas.Special` is a special, `The.Special.2020` and `My.Special.Day` are movies
+        let special = self.regex_special.captures(name).filter(|caps| {
+            let keyword = caps.get(0).unwrap();
+            let rest = name[keyword.end()..].trim_matches(['.', ' ', '_', '-']);
+            rest.is_empty() || self.regex_year.is_match(&name[..keyword.start()])
+        });
+        if let Some(caps) = special {
+            let (prefix, year) = self.split_show_year(&name[..caps.get(0).map_or(0, |m| m.start())]);
+            info.title = if prefix.len() >= 2 { self.clean_title(prefix) } else { self.infer_title_from_folder(path) };
+            info.is_series = true;
+            info.special = true;
+            info.season = Some(0);
+            info.episodes = caps.get(2).and_then(|m| m.as_str().parse().ok()).into_iter().collect();
+            info.episode_title = self.episode_title(&name[caps.get(1).map_or(0, |m| m.start())..]);
+            info.year = year;
+            return;
+        }
+
+        let folders: Vec<&Path> = path.ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != self.config.root_folder.as_path())
+            .take(self.config.path_parse_depth)
+            .collect();
+        let folder_name = |dir: &Path| dir.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+        // An extras folder needs a movie folder above it; a top-level `Shorts/` holds movies
+        let extras_folder = folders.first()
+            .filter(|_| folders.len() > 1)
+            .and_then(|dir| Self::extra_folder(&folder_name(dir)));
+        // Only a keyword after the title and year marks an extra: `Inception 2010 Featurette`,
+        // whereas `The.Interview.2014` and `The.Making.of.a.Murderer.2015` are titles
+        let keyword = self.regex_extra.captures(name)
+            .filter(|c| c.get(0).is_some_and(|m| m.start() > 0 && self.regex_year.is_match(&name[..m.start()])));
+
+        let (folder, keyword_start, extra_name) = match (extras_folder, keyword) {
+            (Some(folder), _) => (folder, None, self.clean_title(name)),
+            (None, Some(caps)) => {
+                let keyword = caps.get(0).unwrap();
+                // `Inception 2010 Featurette Dreams` is the featurette "Dreams", a bare `Trailer` keeps the keyword
+                let rest = name[keyword.end()..].trim_matches(|c: char| matches!(c, '.' | ' ' | '_' | '-'));
+                let extra_name = self.clean_title(if rest.is_empty() { &caps[1] } else { rest });
+                (Self::extra_folder(&caps[1]).unwrap_or("Other"), Some(keyword.start()), extra_name)
+            }
+            (None, None) => return,
+        };
+
+        if keyword_start.is_none() {
+            // `Movie (2010)/Featurettes/Cast Interview.mkv`: the movie is the folder above
+            if let Some(movie_dir) = folders.get(1) {
+                let (movie_name, _) = split_release(&folder_name(movie_dir));
+                let movie = self.parse_layout(movie_dir, &movie_name);
+                info.title = movie.title;
+                info.year = movie.year;
+            }
+        } else if let (Some(start), None) = (keyword_start, &info.year) {
+            info.title = self.clean_title(&name[..start]);
+        }
+
+        info.extra = Some((folder, extra_name));
+    }
+
+    /// Reads the show title and season from up to `path_parse_depth` ancestor folders below
+    /// the root. The nearest season folder gives the season (and the title for season packs
+    /// like `Show.S02.1080p.WEB`), the nearest other folder the title.
+    fn folder_hints(&self, path: &Path) -> FolderHints {
+        let mut hints = FolderHints::default();
+
+        let folders = path.ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != self.config.root_folder.as_path())
+            .take(self.config.path_parse_depth)
+            .filter_map(|dir| dir.file_name().and_then(|n| n.to_str()));
+
+        for folder in folders {
+            let (name, _) = split_release(folder);
+            if let Some(caps) = self.regex_season_folder.captures(&name) {
+                if hints.season.is_none() {
+                    hints.season = caps[2].parse().ok().map(|s| (s, folder.to_string()));
+                }
+                if let Some(show) = caps.get(1).filter(|m| m.as_str().len() >= 2) {
+                    hints.title.get_or_insert((self.clean_title(show.as_str()), folder.to_string()));
+                }
+            } else if hints.title.is_none() {
+                let (show, year) = self.split_show_year(&name);
+                hints.title = Some((self.clean_title(show), folder.to_string()));
+                hints.year = year;
+            }
+
+            if hints.title.is_some() && hints.season.is_some() {
+                break;
+            }
+        }
+
+        hints
+    }
+
+    /// Completes what the file name left open from the folders: `Show.S02.1080p.WEB/E05.mkv`
+    /// becomes Show S02E05. Records the source of every field for `explain_parsing`.
+    fn merge_folders(&self, path: &Path, name: &str, info: &mut MediaInfo) {
+        let hints = self.folder_hints(path);
+        let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let plain = !info.is_series && info.year.is_none();
+
+        if let (true, Some((season, season_folder))) = (plain, &hints.season) {
+            let (title, title_source) = hints.title.clone().unwrap_or_else(|| ("Unknown".to_string(), file.clone()));
+            let mut merged = MediaInfo {
+                title,
+                is_series: true,
+                season: Some(*season),
+                ..Default::default()
+            };
+            if let Some(year) = &hints.year {
+                merged.year = Some(year.clone());
+                merged.sources.push(("year", title_source.clone()));
+            }
+            merged.sources.push(("title", title_source));
+            merged.sources.push(("season", season_folder.clone()));
+
+            if let Some(caps) = self.regex_episode_only.captures(name) {
+                let first = caps.get(1).or(caps.get(2)).and_then(|m| m.as_str().parse().ok());
+                if let Some(first) = first {
+                    let (episodes, rest) = self.episode_list(first, caps.get(3).map_or("", |m| m.as_str()));
+                    merged.episodes = episodes;
+                    merged.episode_title = self.episode_title(rest);
+                }
+            }
+
+            merged.release = std::mem::take(&mut info.release);
+            merged.edition = info.edition.take();
+            merged.crc32 = info.crc32.take();
+            *info = merged;
+        } else if let Some((_, folder)) = hints.title.filter(|(t, _)| *t == info.title) {
+            // Only credit the folder when the file name itself does not carry the title
+            if !self.clean_title(name).starts_with(&info.title) {
+                info.sources.push(("title", folder.clone()));
+            }
+            // `Doctor Who (2005)/Season 1/Doctor.Who.S01E01.mkv`
+            if info.is_series && info.year.is_none() && hints.year.is_some() {
+                info.year = hints.year;
+                info.sources.push(("year", folder));
+            }
+        }
+
+        let fields = [
+            ("title", !info.title.is_empty()),
+            ("year", info.year.is_some()),
+            ("season", info.season.is_some()),
+            ("episode", !info.episodes.is_empty()),
+        ];
+        for (field, present) in fields {
+            if present && !info.sources.iter().any(|(f, _)| *f == field) {
+                info.sources.push((field, file.clone()));
+            }
+        }
+    }
+
+    /// Parses `Show Name - 137v2 - Title` with an absolute episode number. The number is kept as
+    /// `S01E137` unless `anime_season_lengths` lists the show, in which case it is mapped to
+    /// the season and episode it falls into.
+    fn parse_absolute(&self, name: &str) -> Option<MediaInfo> {
+        let caps = self.regex_absolute.captures(name)?;
+        let title = self.clean_title(&caps[1]);
+        let absolute: u32 = caps[2].parse().ok()?;
+
+        let lengths = self.config.anime_season_lengths.iter()
+            .find(|(show, _)| show.eq_ignore_ascii_case(&title))
+            .map(|(_, lengths)| lengths.as_slice())
+            .unwrap_or_default();
+        let (mut season, mut episode) = (1, absolute);
+        for &length in lengths {
+            if episode <= length {
+                break;
+            }
+            episode -= length;
+            season += 1;
+        }
+
+        Some(MediaInfo {
+            title,
+            is_series: true,
+            season: Some(season),
+            episodes: vec![episode],
+            episode_title: caps.get(4).and_then(|m| self.episode_title(m.as_str())),
+            absolute: Some(absolute),
+            version: caps.get(3).and_then(|m| m.as_str().parse().ok()),
+            ..Default::default()
+        })
+    }
+
+    /// Whatever follows the episode marker once release tags are gone, e.g. "The Title" in
+    /// `Show.S01E02.The.Title.1080p.WEB`.
+    fn episode_title(&self, rest: &str) -> Option<String> {
+        let title = self.clean_title(rest);
+        (!title.is_empty()).then_some(title)
+    }
+
+    /// Reads further episode numbers after the first one: `E02` in `S01E01E02`, a range in
+    /// `S01E01-E03` or `1x01-03`. Returns the sorted episodes and the rest of the name.
+    fn episode_list<'a>(&self, first: u32, mut rest: &'a str) -> (Vec<u32>, &'a str) {
+        let mut episodes = vec![first];
+
+        while let Some(caps) = self.regex_episode_more.captures(rest) {
+            let end = caps.get(0).map_or(0, |m| m.end());
+            // `S01E01-720p` or `E01-2nd` are not episode numbers
+            if rest[end..].starts_with(|c: char| c.is_ascii_alphanumeric()) {
+                break;
+            }
+            let last = *episodes.last().unwrap();
+            let Ok(n) = caps[1].parse::<u32>() else { break };
+            if n <= last {
+                break;
+            }
+
+            if caps[0].contains('-') {
+                if n - last > MAX_EPISODE_SPAN {
+                    break;
+                }
+                // A bare `-10` may be the start of the title (`S01E05-10.Things.I.Hate`), so it only
+                // ends a range when nothing but release tags follows
+                let after = rest[end..].trim_start_matches(['.', ' ', '_', '-']);
+                let bare = !caps[0].contains(['e', 'E']);
+                if bare && !after.is_empty() && !starts_with_tag(after) {
+                    break;
+                }
+                episodes.extend(last + 1..=n);
+            } else {
+                episodes.push(n);
+            }
+            rest = &rest[end..];
+        }
+
+        (episodes, rest)
+    }
+
+    /// Splits the year off a show title so remakes keep it apart: `Doctor.Who.2005` gives
+    /// `Doctor.Who` and 2005.
+    fn split_show_year<'a>(&self, raw: &'a str) -> (&'a str, Option<String>) {
+        match self.regex_show_year.captures(raw) {
+            Some(caps) => (caps.get(1).map_or(raw, |m| m.as_str()), Some(caps[2].to_string())),
+            None => (raw, None),
+        }
+    }
+
+    /// Detects series/movie, title, season, episode and year from the file name and folders.
+    fn parse_layout(&self, path: &Path, filename: &str) -> MediaInfo {
         if let Some(caps) = self.regex_series_standard.captures(filename) {
-            let raw = caps.get(1).map_or("", |m| m.as_str());
+            let (raw, year) = self.split_show_year(caps.get(1).map_or("", |m| m.as_str()));
             let season = caps.get(2).map_or("1", |m| m.as_str());
+            let rest = &filename[caps.get(0).map_or(0, |m| m.end())..];
+            let (episodes, rest) = match caps.get(3).and_then(|m| m.as_str().parse().ok()) {
+                Some(first) => self.episode_list(first, rest),
+                None => (Vec::new(), rest),
+            };
+            let episode_title = self.episode_title(rest);
             
             let title = if raw.len() < 2 {
                 self.infer_title_from_folder(path)
@@ -71,24 +596,40 @@ impl Processor {
 
             return MediaInfo {
                 title,
-                season_folder: format!("Season {:0>2}", season),
                 is_series: true,
-                year: None,
+                year,
+                season: season.parse().ok(),
+                episodes,
+                episode_title,
+                ..Default::default()
             };
         }
 
         if let Some(caps) = self.regex_series_x.captures(filename) {
-            let raw = caps.get(1).map_or("", |m| m.as_str());
+            let (raw, year) = self.split_show_year(caps.get(1).map_or("", |m| m.as_str()));
             let season = caps.get(2).map_or("1", |m| m.as_str());
+            let rest = &filename[caps.get(0).map_or(0, |m| m.end())..];
+            let (episodes, rest) = match caps.get(3).and_then(|m| m.as_str().parse().ok()) {
+                Some(first) => self.episode_list(first, rest),
+                None => (Vec::new(), rest),
+            };
+            let episode_title = self.episode_title(rest);
             
             return MediaInfo {
                 title: self.clean_title(raw),
-                season_folder: format!("Season {:0>2}", season),
                 is_series: true,
-                year: None,
+                year,
+                season: season.parse().ok(),
+                episodes,
+                episode_title,
+                ..Default::default()
             };
         }
 
+        if let Some(info) = self.parse_air_date(path, filename) {
+            return info;
+        }
+
         if let Some(caps) = self.regex_year.captures(filename) {
             let raw = caps.get(1).map_or("", |m| m.as_str());
             let year = caps.get(2).map_or("", |m| m.as_str());
@@ -101,122 +642,608 @@ impl Processor {
 
             return MediaInfo {
                 title,
-                season_folder: String::new(),
                 is_series: false,
                 year: Some(year.to_string()),
+                ..Default::default()
             };
         }
 
-        if let Some(parent) = path.parent() {
-            let parent_name = parent.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if let Some(caps) = self.regex_season_only.captures(parent_name) {
-                let season = caps.get(1).map_or("1", |m| m.as_str());
-                return MediaInfo {
-                    title: self.infer_title_from_folder(path),
-                    season_folder: format!("Season {:0>2}", season),
-                    is_series: true,
-                    year: None,
-                };
-            }
-        }
-
         MediaInfo {
             title: self.clean_title(filename),
-            season_folder: String::new(),
             is_series: false,
-            year: None,
+            ..Default::default()
+        }
+    }
+
+    /// Daily shows named by air date: `Show.2024.03.15.Guest` or `Show.15.03.2024`. The year
+    /// becomes the season. `DD.MM` is swapped when only that reading makes a valid date.
+    fn parse_air_date(&self, path: &Path, filename: &str) -> Option<MediaInfo> {
+        let (caps, year, month, day) = if let Some(caps) = self.regex_date_ymd.captures(filename) {
+            let (y, m, d) = (caps[2].to_string(), caps[3].to_string(), caps[4].to_string());
+            (caps, y, m, d)
+        } else {
+            let caps = self.regex_date_dmy.captures(filename)?;
+            let (d, m, y) = (caps[2].to_string(), caps[3].to_string(), caps[4].to_string());
+            if m.parse::<u32>().ok()? > 12 {
+                (caps, y, d, m)
+            } else {
+                (caps, y, m, d)
+            }
+        };
+
+        let (m, d): (u32, u32) = (month.parse().ok()?, day.parse().ok()?);
+        if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+            return None;
         }
+
+        let raw = caps.get(1).map_or("", |m| m.as_str());
+        let title = if raw.len() < 2 { self.infer_title_from_folder(path) } else { self.clean_title(raw) };
+        let rest = &filename[caps.get(0).map_or(0, |m| m.end())..];
+
+        Some(MediaInfo {
+            title,
+            is_series: true,
+            season: year.parse().ok(),
+            episode_title: self.episode_title(rest),
+            air_date: Some(format!("{}-{}-{}", year, month, day)),
+            year: Some(year),
+            ..Default::default()
+        })
     }
 
     fn infer_title_from_folder(&self, path: &Path) -> String {
-        let components: Vec<_> = path.components()
-            .map(|c| c.as_os_str().to_string_lossy())
-            .collect();
-        
-        let len = components.len();
-        if len < 3 { return "Unknown".to_string(); }
-        
-        let parent = &components[len - 2];
-        if self.regex_season_only.is_match(parent) {
-            self.clean_title(&components[len - 3])
+        self.folder_hints(path).title.map_or_else(|| "Unknown".to_string(), |(title, _)| title)
+    }
+
+    /// Picks the fonts used by the ASS/SSA subtitles, returning the files to attach and the
+    /// names of referenced fonts that none of the available files provide.
+    fn select_fonts(&self, assets: &FoundAssets) -> (Vec<PathBuf>, BTreeSet<String>) {
+        let mut referenced = BTreeSet::new();
+        for sub in &assets.subtitles {
+            let ext = sub.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+            if ext == "ass" || ext == "ssa" {
+                referenced.extend(referenced_fonts(sub));
+            }
+        }
+        if referenced.is_empty() {
+            return (Vec::new(), referenced);
+        }
+
+        let mut attach = Vec::new();
+        let mut missing = referenced.clone();
+        let mut cache = self.font_names.lock().unwrap();
+        for font in &assets.fonts {
+            let names = cache.entry(font.clone()).or_insert_with(|| font_names(font));
+            if names.iter().any(|n| referenced.contains(n)) {
+                attach.push(font.clone());
+                missing.retain(|m| !names.contains(m));
+            }
+        }
+
+        (attach, missing)
+    }
+
+    /// Attachment file name extension and MIME type for a cover image.
+    fn cover_format(cover: &CoverArt) -> (&'static str, &'static str) {
+        match cover.path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("png") => ("png", "image/png"),
+            _ => ("jpg", "image/jpeg"),
+        }
+    }
+
+    /// Copies the main cover next to the output: `<name>-poster.jpg` for per-video art,
+    /// `poster.jpg` in the movie or show folder for folder art. Existing posters are kept.
+    fn copy_cover(&self, cover: &CoverArt, output_file: &Path, is_series: bool) -> std::io::Result<()> {
+        let (ext, _) = Self::cover_format(cover);
+        let Some(target_dir) = output_file.parent() else { return Ok(()) };
+
+        let dest = if cover.per_video {
+            let stem = output_file.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+            target_dir.join(format!("{}-poster.{}", stem, ext))
+        } else if is_series {
+            target_dir.parent().unwrap_or(target_dir).join(format!("poster.{}", ext))
         } else {
-            self.clean_title(parent)
+            target_dir.join(format!("poster.{}", ext))
+        };
+
+        if !dest.exists() {
+            fs::copy(&cover.path, dest)?;
         }
+        Ok(())
     }
 
-    pub fn process_file(&self, video_path: &Path) -> ProcessStatus {
-        let meta = self.parse_media_info(video_path);
-        
-        let target_dir = if meta.is_series {
-            self.config.output_root
-                .join("TV Shows")
-                .join(&meta.title)
-                .join(&meta.season_folder)
+    /// Language of an audio track: its own tag if set, otherwise what the file name says.
+    fn audio_track_language(track: &TrackInfo, file_lang: &LangDetectResult) -> LangDetectResult {
+        track.language().and_then(lookup_language).unwrap_or_else(|| file_lang.clone())
+    }
+
+    /// Adds language, name and default flag options for one subtitle track of the next input file.
+    /// Bitmap formats get their format appended to the track name, e.g. "English (PGS)".
+    fn add_subtitle_track(&self, cmd: &mut Command, tid: usize, lang: &LangDetectResult, format: Option<&str>) {
+        let is_default = if lang.iso == self.config.default_sub_lang { "1" } else { "0" };
+        let name = match format {
+            Some(f) => format!("{} ({})", lang.name, f),
+            None => lang.name.clone(),
+        };
+
+        cmd.arg("--language").arg(format!("{}:{}", tid, lang.iso))
+           .arg("--track-name").arg(format!("{}:{}", tid, name))
+           .arg("--default-track").arg(format!("{}:{}", tid, is_default));
+    }
+
+    /// The first routing rule matching the file. Audio track languages of the video are only
+    /// read when a rule asks for a language.
+    fn route_for(&self, meta: &MediaInfo, video_path: &Path, probe: &VideoProbe) -> Option<&Route> {
+        if self.routes.is_empty() {
+            return None;
+        }
+
+        let mut languages: Vec<String> = meta.original_language.iter().cloned()
+            .chain(meta.release.languages.iter().filter_map(|l| lookup_language(l)).map(|l| l.iso))
+            .collect();
+        if self.routes.iter().any(Route::needs_languages) {
+            if let Ok(info) = self.probe_video(video_path, probe) {
+                languages.extend(info.tracks.iter().filter(|t| t.is_audio()).filter_map(|t| lookup_language(t.language()?)).map(|l| l.iso));
+            }
+        }
+
+        let input = RouteInput {
+            relative_path: video_path.strip_prefix(&self.config.root_folder).unwrap_or(video_path),
+            title: &meta.title,
+            category: meta.alias.as_ref().and_then(|a| a.category.as_deref()),
+            languages: &languages,
+        };
+        self.routes.iter().find(|route| route.matches(&input))
+    }
+
+    /// Probes the video on first use; later calls of the same job reuse the answer.
+    fn probe_video<'a>(&self, video_path: &Path, probe: &'a VideoProbe) -> Result<&'a Identification, &'a String> {
+        probe.get_or_init(|| identify(&self.config.mkvmerge_path, video_path).map_err(|e| e.to_string())).as_ref()
+    }
+
+    /// Builds the output path from the naming templates, under the route's library root when
+    /// a routing rule matched. Episodes without an episode number
+    /// keep their original file name, since the episode template cannot be filled for them.
+    fn output_path(&self, meta: &MediaInfo, video_path: &Path, route: Option<&Route>) -> PathBuf {
+        let naming = route.map_or(&self.naming, |r| &r.naming);
+        let mut values = HashMap::new();
+        values.insert("title", TemplateValue::Text(meta.title.clone()));
+        values.insert("ext", TemplateValue::Text("mkv".to_string()));
+        let optional = [
+            ("year", meta.year.clone().map(TemplateValue::Text)),
+            ("season", meta.season.map(TemplateValue::Number)),
+            ("episode", (!meta.episodes.is_empty()).then(|| TemplateValue::Episodes(meta.episodes.clone()))),
+            ("episode_title", meta.episode_title.clone().map(TemplateValue::Text)),
+            ("air_date", meta.air_date.clone().map(TemplateValue::Text)),
+            ("absolute", meta.absolute.map(TemplateValue::Number)),
+            ("resolution", meta.release.resolution.clone().map(TemplateValue::Text)),
+            ("source", meta.release.source.clone().map(TemplateValue::Text)),
+            ("codec", meta.release.video_codec.clone().map(TemplateValue::Text)),
+            ("audio", meta.release.audio.clone().map(TemplateValue::Text)),
+            ("hdr", (!meta.release.hdr.is_empty()).then(|| TemplateValue::Text(meta.release.hdr.join(" ")))),
+            ("edition", meta.edition.clone().map(TemplateValue::Text)),
+            ("group", meta.release.group.clone().map(TemplateValue::Text)),
+        ];
+        values.extend(optional.into_iter().filter_map(|(k, v)| Some((k, v?))));
+        if meta.edition.is_some() {
+            let tag = render(&naming.edition, &values).join(" ");
+            values.insert("edition_tag", TemplateValue::Text(tag));
+        }
+
+        let (library, folder_template, file_template) = if meta.air_date.is_some() {
+            ("TV Shows", &naming.daily_folder, &naming.daily_file)
+        } else if meta.is_series {
+            ("TV Shows", &naming.series_folder, &naming.episode_file)
         } else {
-            let folder_name = if let Some(y) = meta.year {
-                format!("{} ({})", meta.title, y)
-            } else {
-                meta.title.clone()
-            };
-            self.config.output_root
-                .join("Movies")
-                .join(folder_name)
+            ("Movies", &naming.movie_folder, &naming.movie_file)
+        };
+
+        let mut dir = match route {
+            Some(route) => route.root.clone(),
+            None => self.config.output_root.join(library),
         };
+        let mut folders = render(folder_template, &values);
+        if meta.special && !self.config.specials_folder.is_empty() {
+            // Replace the season folder, or add one when the template has none
+            if folders.len() > 1 {
+                folders.pop();
+            }
+            folders.push(self.config.specials_folder.clone());
+        }
+        dir.extend(folders);
+
+        if let Some((extras_folder, extra_name)) = &meta.extra {
+            return dir.join(extras_folder).join(format!("{}.mkv", extra_name));
+        }
 
         let stem = video_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
-        let output_file = target_dir.join(format!("{}.mkv", stem));
+        let unnumbered = meta.is_series && meta.episodes.is_empty() && meta.air_date.is_none();
+        let mut file_name = if unnumbered && meta.special && meta.episode_title.is_some() {
+            render(&without_episode(file_template), &values).join(" ")
+        } else if unnumbered {
+            String::new()
+        } else {
+            render(file_template, &values).join(" ")
+        };
+        if file_name.is_empty() {
+            file_name = stem.to_string();
+        }
+        if !file_name.to_lowercase().ends_with(".mkv") {
+            file_name.push_str(".mkv");
+        }
+
+        dir.join(file_name)
+    }
+
+    /// Joins the subtitles of a multi-part movie, shifting each part's subtitles by the playing
+    /// time of the parts before it. Subtitles are joined per format, language and position, so a
+    /// forced and a full English track stay separate. Subtitles of the first part only are used
+    /// as they are; the joined files are written to the temp folder and listed in `temp_files`.
+    /// A joined file comes with the language of its first source, which a `Subs/Finnish/` folder
+    /// may have supplied and the temp copy no longer shows.
+    fn join_part_subtitles(
+        &self,
+        parts: &[PathBuf],
+        part_assets: &[FoundAssets],
+        warnings: &mut Vec<String>,
+        temp_files: &mut Vec<PathBuf>,
+    ) -> Result<Vec<(PathBuf, Option<LangDetectResult>)>, String> {
+        let mut starts = vec![0i64];
+        for part in &parts[..parts.len() - 1] {
+            let part_name = part.file_name().unwrap_or_default().to_string_lossy();
+            let duration = identify(&self.config.mkvmerge_path, part)
+                .map_err(|e| format!("Cannot join parts, {}: {}", part_name, e))?
+                .container
+                .duration_ms()
+                .ok_or_else(|| format!("Cannot join parts, unknown duration of {}", part_name))?;
+            starts.push(starts[starts.len() - 1] + duration);
+        }
+
+        let mut subtitles = Vec::new();
+        // (part, subtitle, offset) per (format, language, n-th of that kind within its part)
+        type Entry<'a> = (usize, &'a Path, i64);
+        let mut groups: Vec<((String, String, usize), Vec<Entry>)> = Vec::new();
+        for (part, assets) in part_assets.iter().enumerate() {
+            let mut seen: HashMap<(String, String), usize> = HashMap::new();
+            for sub in &assets.subtitles {
+                if !is_joinable(sub) {
+                    if part == 0 {
+                        subtitles.push((sub.clone(), None));
+                    } else {
+                        warnings.push(format!("Skipped {}: only text subtitles can be joined", sub.file_name().unwrap_or_default().to_string_lossy()));
+                    }
+                    continue;
+                }
+                let ext = sub.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+                let kind = (ext, detect_subtitle_language(sub).iso);
+                let nth = seen.entry(kind.clone()).or_default();
+                let key = (kind.0, kind.1, *nth);
+                *nth += 1;
+
+                let offset = starts[part] + sync_offset(sub, &self.config).unwrap_or(0);
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, entries)) => entries.push((part, sub.as_path(), offset)),
+                    None => groups.push((key, vec![(part, sub.as_path(), offset)])),
+                }
+            }
+        }
+
+        for (_, entries) in groups {
+            if entries.iter().all(|(part, _, _)| *part == 0) {
+                subtitles.extend(entries.iter().map(|(_, sub, _)| (sub.to_path_buf(), None)));
+                continue;
+            }
+            // Keep the original name so the joined file is recognizable in the temp folder
+            let first = entries[0].1;
+            let joined = std::env::temp_dir().join(format!(
+                "mkv_pro_manager_{}_{}_{}",
+                std::process::id(),
+                TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+                first.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            let sources: Vec<_> = entries.iter().map(|(_, sub, offset)| (*sub, *offset)).collect();
+            join_subtitles(&sources, &joined).map_err(|e| format!("Subtitle Join Error: {}", e))?;
+            temp_files.push(joined.clone());
+            subtitles.push((joined, Some(detect_subtitle_language(first))));
+        }
+
+        Ok(subtitles)
+    }
+
+    /// Remuxes one job: a single video, or the parts of a multi-part movie appended in order.
+    /// `part_assets` holds the assets assigned to each part.
+    pub fn process_file(&self, parts: &[PathBuf], part_assets: &[FoundAssets]) -> ProcessStatus {
+        let video_path = parts[0].as_path();
+        let assets = &part_assets[0];
+        let meta = self.parse_media_info(video_path);
+
+        let probe = VideoProbe::new();
+        let route = self.route_for(&meta, video_path, &probe);
+        let output_file = self.output_path(&meta, video_path, route);
+        let target_dir = output_file.parent().unwrap_or(&self.config.output_root).to_path_buf();
 
         if output_file.exists() {
             return ProcessStatus::Skipped;
         }
 
-        let assets = find_matching_assets(video_path, &self.config);
+        // A mismatching checksum means a broken download: leave it alone instead of remuxing it.
+        // Hashing reads every byte, so a dry run skips it.
+        if self.config.verify_crc32 && !self.config.dry_run {
+            for part in parts {
+                let Some(expected) = self.parse_media_info(part).crc32 else { continue };
+                match file_crc32(part) {
+                    Ok(crc) => {
+                        let actual = format!("{:08X}", crc);
+                        if actual != expected {
+                            return ProcessStatus::Corrupt { expected, actual };
+                        }
+                    }
+                    Err(e) => return ProcessStatus::Failed(format!("CRC Read Error: {}", e)),
+                }
+            }
+        }
+
+        // Identify external audio up front so unreadable files are dropped instead of failing the merge
+        let mut warnings = Vec::new();
+        let mut audios = Vec::new();
+        for audio in &assets.audios {
+            let audio_name = audio.file_name().unwrap_or_default().to_string_lossy();
+            match identify(&self.config.mkvmerge_path, audio) {
+                Ok(info) => {
+                    let tracks: Vec<_> = info.tracks.into_iter().filter(|t| t.is_audio()).collect();
+                    if tracks.is_empty() {
+                        warnings.push(format!("Skipped audio {}: no audio tracks", audio_name));
+                    } else {
+                        audios.push((audio, tracks));
+                    }
+                }
+                Err(e) => warnings.push(format!("Skipped audio {}: {}", audio_name, e)),
+            }
+        }
+
+        let mut notes = Vec::new();
+        let mut offsets: HashMap<&Path, i64> = HashMap::new();
+        for asset in assets.subtitles.iter().chain(audios.iter().map(|(a, _)| *a)) {
+            if let Some(ms) = sync_offset(asset, &self.config) {
+                notes.push(format!("Sync {}: {:+} ms", asset.file_name().unwrap_or_default().to_string_lossy(), ms));
+                offsets.insert(asset.as_path(), ms);
+            }
+        }
+
+        // The original-language audio becomes the default track, from the video or an external file
+        let mut video_audio = Vec::new();
+        let mut default_audio: Option<(&Path, usize)> = None;
+        if let Some(original) = meta.original_language.as_deref().and_then(lookup_language) {
+            match self.probe_video(video_path, &probe) {
+                Ok(info) => video_audio = info.tracks.iter().filter(|t| t.is_audio()).collect(),
+                Err(e) => warnings.push(format!("Could not read audio tracks: {}", e)),
+            }
+            default_audio = video_audio.iter()
+                .find(|t| t.language().and_then(lookup_language).is_some_and(|l| l.iso == original.iso))
+                .map(|t| (video_path, t.id))
+                .or_else(|| audios.iter().find_map(|(audio, tracks)| {
+                    let file_lang = detect_audio_language(audio);
+                    tracks.iter()
+                        .find(|t| Self::audio_track_language(t, &file_lang).iso == original.iso)
+                        .map(|t| (audio.as_path(), t.id))
+                }));
+            if default_audio.is_some() {
+                notes.push(format!("Default audio: {} (original language)", original.name));
+            }
+        }
+
+        // Chapters: an external file wins, otherwise optionally generate them for chapterless sources
+        let chapter_file = assets.chapters.first();
+        let mut generate_chapters = false;
+        if let Some(chapters) = chapter_file {
+            notes.push(format!("Chapters from {}", chapters.file_name().unwrap_or_default().to_string_lossy()));
+            for extra in &assets.chapters[1..] {
+                warnings.push(format!("Ignored extra chapter file {}", extra.file_name().unwrap_or_default().to_string_lossy()));
+            }
+        } else if !self.config.chapter_interval.is_empty() {
+            match self.probe_video(video_path, &probe) {
+                Ok(info) if info.has_chapters() => {}
+                Ok(_) => {
+                    generate_chapters = true;
+                    notes.push(format!("Generated chapters every {}", self.config.chapter_interval));
+                }
+                Err(e) => warnings.push(format!("Could not check chapters: {}", e)),
+            }
+        }
+
+        // External audio and chapters cannot be appended, they only cover their own part
+        for later in &part_assets[1..] {
+            for asset in later.audios.iter().chain(&later.chapters) {
+                warnings.push(format!("Skipped {}: only subtitles of later parts are joined", asset.file_name().unwrap_or_default().to_string_lossy()));
+            }
+        }
+
+        let (fonts, missing_fonts) = self.select_fonts(assets);
+        if !fonts.is_empty() {
+            notes.push(format!("Attached {} font(s)", fonts.len()));
+        }
+        for name in &missing_fonts {
+            warnings.push(format!("Missing font: {}", name));
+        }
+
+        for cover in &assets.covers {
+            let kind = if cover.small { "Small cover" } else { "Cover" };
+            notes.push(format!("{} from {}", kind, cover.path.file_name().unwrap_or_default().to_string_lossy()));
+        }
+
+        if parts.len() > 1 {
+            notes.push(format!("Joined {} parts", parts.len()));
+        }
+        notes.push(format!("Title: {}", meta.segment_title()));
+        if self.config.explain_parsing {
+            let sources: Vec<_> = meta.sources.iter().map(|(field, from)| format!("{} ← {}", field, from)).collect();
+            notes.push(format!("Parsed: {}", sources.join(", ")));
+        }
+        if let Some(absolute) = meta.absolute {
+            let version = meta.version.map(|v| format!(" v{}", v)).unwrap_or_default();
+            notes.push(format!("Absolute episode {}{}", absolute, version));
+        }
+        if !meta.release.is_empty() {
+            notes.push(format!("Release: {}", meta.release.summary()));
+        }
+        if let Some(category) = meta.alias.as_ref().and_then(|a| a.category.as_ref()) {
+            notes.push(format!("Category: {}", category));
+        }
+        if let Some(route) = route {
+            notes.push(format!("Library: {}", route.name));
+        }
 
         if self.config.dry_run {
-            return ProcessStatus::Success { subs: assets.subtitles.len(), audios: assets.audios.len() }; 
+            return ProcessStatus::Success { subs: assets.subtitles.len(), audios: audios.len(), notes, warnings };
         }
 
         if let Err(e) = fs::create_dir_all(&target_dir) {
             return ProcessStatus::Failed(format!("Dir Create Error: {}", e));
         }
 
+        let mut temp_files = Vec::new();
+        let subtitles = if parts.len() > 1 {
+            match self.join_part_subtitles(parts, part_assets, &mut warnings, &mut temp_files) {
+                Ok(subtitles) => subtitles,
+                Err(e) => {
+                    for path in &temp_files { let _ = fs::remove_file(path); }
+                    return ProcessStatus::Failed(e);
+                }
+            }
+        } else {
+            assets.subtitles.iter().map(|sub| (sub.clone(), None)).collect()
+        };
+
         let mut cmd = Command::new(&self.config.mkvmerge_path);
-        cmd.arg("-o").arg(&output_file).arg(video_path);
+        cmd.arg("-o").arg(&output_file);
+        cmd.arg("--title").arg(meta.segment_title());
 
-        for sub in &assets.subtitles {
-            let lang = detect_subtitle_language(sub);
-            let is_default = if lang.iso == self.config.default_sub_lang { "1" } else { "0" };
-            
-            cmd.arg("--language").arg(format!("0:{}", lang.iso))
-               .arg("--track-name").arg(format!("0:{}", lang.name))
-               .arg("--default-track").arg(format!("0:{}", is_default))
-               .arg(sub);
+        let tags_file = if self.config.write_global_tags {
+            let season_total = meta.catalog_season_size
+                .or_else(|| meta.season.and_then(|s| self.season_sizes.get(&(meta.title.clone(), s)).copied()));
+            let path = std::env::temp_dir().join(format!(
+                "mkv_pro_manager_{}_{}.xml",
+                std::process::id(),
+                TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            if let Err(e) = fs::write(&path, global_tags_xml(&meta, season_total)) {
+                for path in &temp_files { let _ = fs::remove_file(path); }
+                return ProcessStatus::Failed(format!("Tags Write Error: {}", e));
+            }
+            cmd.arg("--global-tags").arg(&path);
+            Some(path)
+        } else {
+            None
+        };
+
+        if let Some(chapters) = chapter_file {
+            cmd.arg("--chapters").arg(chapters);
+        } else if generate_chapters {
+            cmd.arg("--generate-chapters").arg(format!("interval:{}", self.config.chapter_interval));
         }
 
-        for audio in &assets.audios {
-            let name = audio.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
-            let (iso, lang_name) = if name.contains("fin") || name.contains("suomi") {
-                ("fin", "Finnish")
-            } else if name.contains("eng") || name.contains("english") {
-                ("eng", "English")
-            } else {
-                ("eng", "English") // Default to English for now
+        if default_audio.is_some() {
+            for track in &video_audio {
+                let is_default = if default_audio == Some((video_path, track.id)) { "1" } else { "0" };
+                cmd.arg("--default-track").arg(format!("{}:{}", track.id, is_default));
+            }
+        }
+        cmd.arg(video_path);
+        for part in &parts[1..] {
+            cmd.arg("+").arg(part);
+        }
+
+        for (sub, known_lang) in &subtitles {
+            let lang = || known_lang.clone().unwrap_or_else(|| detect_subtitle_language(sub));
+            let ext = sub.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+            let track_count = match ext.as_str() {
+                // VobSub: one track per `id:` line, the `.sub` half is picked up by mkvmerge itself
+                "idx" => {
+                    let mut langs = read_idx_languages(sub);
+                    if langs.is_empty() {
+                        langs.push(lang());
+                    }
+                    for (tid, lang) in langs.iter().enumerate() {
+                        self.add_subtitle_track(&mut cmd, tid, lang, Some("VobSub"));
+                    }
+                    langs.len()
+                }
+                "sup" => {
+                    self.add_subtitle_track(&mut cmd, 0, &lang(), Some("PGS"));
+                    1
+                }
+                _ => {
+                    self.add_subtitle_track(&mut cmd, 0, &lang(), None);
+                    1
+                }
             };
+            if let Some(ms) = offsets.get(sub.as_path()) {
+                for tid in 0..track_count {
+                    cmd.arg("--sync").arg(format!("{}:{}", tid, ms));
+                }
+            }
+            cmd.arg(sub);
+        }
+
+        for (audio, tracks) in &audios {
+            let lang = detect_audio_language(audio);
+            let offset = offsets.get(audio.as_path());
+
+            for track in tracks {
+                let track_lang = Self::audio_track_language(track, &lang);
+
+                cmd.arg("--language").arg(format!("{}:{}", track.id, track_lang.iso))
+                   .arg("--track-name").arg(format!("{}:{} – {}", track.id, track_lang.name, track.codec_label()));
+                if default_audio.is_some() {
+                    let is_default = if default_audio == Some((audio.as_path(), track.id)) { "1" } else { "0" };
+                    cmd.arg("--default-track").arg(format!("{}:{}", track.id, is_default));
+                }
+                if let Some(ms) = offset {
+                    cmd.arg("--sync").arg(format!("{}:{}", track.id, ms));
+                }
+            }
+            cmd.arg(audio);
+        }
+
+        for font in &fonts {
+            cmd.arg("--attachment-mime-type").arg(font_mime_type(font))
+               .arg("--attach-file").arg(font);
+        }
+
+        for cover in &assets.covers {
+            let (ext, mime) = Self::cover_format(cover);
+            let name = if cover.small { "small_cover" } else { "cover" };
+            cmd.arg("--attachment-name").arg(format!("{}.{}", name, ext))
+               .arg("--attachment-mime-type").arg(mime)
+               .arg("--attach-file").arg(&cover.path);
+        }
 
-            cmd.arg("--language").arg(format!("0:{}", iso)) 
-               .arg("--track-name").arg(format!("0:{}", lang_name))
-               .arg(audio);
+        let result = cmd.output();
+        for path in tags_file.iter().chain(&temp_files) {
+            let _ = fs::remove_file(path);
         }
 
-        match cmd.output() {
+        match result {
             Ok(output) => {
                 if output.status.success() {
+                     if self.config.copy_cover_art {
+                         for cover in assets.covers.iter().filter(|c| !c.small) {
+                             if let Err(e) = self.copy_cover(cover, &output_file, meta.is_series) {
+                                 warnings.push(format!("Cover copy failed: {}", e));
+                             }
+                         }
+                     }
                      if self.config.delete_originals {
-                         let _ = fs::remove_file(video_path);
-                         for s in &assets.subtitles { let _ = fs::remove_file(s); }
-                         for a in &assets.audios { let _ = fs::remove_file(a); }
+                         for part in parts { let _ = fs::remove_file(part); }
+                         // Subtitles of later parts that could not be joined are kept
+                         let muxed_subs = part_assets.iter().enumerate()
+                             .flat_map(|(i, a)| a.subtitles.iter().filter(move |s| i == 0 || is_joinable(s)));
+                         for s in muxed_subs { let _ = fs::remove_file(s); }
+                         for (a, _) in &audios { let _ = fs::remove_file(a); }
+                         for c in assets.chapters.iter().take(1).chain(&assets.companions) { let _ = fs::remove_file(c); }
+                         for c in assets.covers.iter().filter(|c| c.per_video) { let _ = fs::remove_file(&c.path); }
                      }
-                     ProcessStatus::Success { subs: assets.subtitles.len(), audios: assets.audios.len() }
+                     ProcessStatus::Success { subs: subtitles.len(), audios: audios.len(), notes, warnings }
                 } else {
                     let err = String::from_utf8_lossy(&output.stderr);
                     ProcessStatus::Failed(err.trim().to_string())
@@ -226,3 +1253,176 @@ impl Processor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor() -> Processor {
+        let config = Config {
+            root_folder: PathBuf::from("/in"),
+            output_root: PathBuf::from("/out"),
+            ..Config::default()
+        };
+        let naming = NamingTemplates::from_config(&config).unwrap();
+        Processor::new(config, naming)
+    }
+
+    /// Output path of a source file, relative to the output root.
+    fn output(path: &str) -> String {
+        let processor = processor();
+        let path = Path::new(path);
+        let meta = processor.parse_media_info(path);
+        let output = processor.output_path(&meta, path, None);
+        output.strip_prefix("/out").unwrap().to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn extras_keyword_in_title_is_not_an_extra() {
+        assert_eq!(output("/in/The.Interview.2014.1080p.BluRay.x264-GRP.mkv"), "Movies/The Interview (2014)/The Interview (2014).mkv");
+        assert_eq!(
+            output("/in/The Interview (2014)/The.Interview.2014.1080p.BluRay.x264-GRP.mkv"),
+            "Movies/The Interview (2014)/The Interview (2014).mkv"
+        );
+        assert_eq!(
+            output("/in/The.Making.of.a.Murderer.2015.mkv"),
+            "Movies/The Making of a Murderer (2015)/The Making of a Murderer (2015).mkv"
+        );
+    }
+
+    #[test]
+    fn extras_are_named_after_the_keyword() {
+        assert_eq!(output("/in/Inception 2010 Featurette Dreams.mkv"), "Movies/Inception (2010)/Featurettes/Dreams.mkv");
+        assert_eq!(output("/in/Deadpool 2016 Trailer.mkv"), "Movies/Deadpool (2016)/Trailers/Trailer.mkv");
+    }
+
+    #[test]
+    fn extras_folder_takes_the_movie_from_above() {
+        assert_eq!(output("/in/Film (2010)/Featurettes/Cast Interview.mkv"), "Movies/Film (2010)/Featurettes/Cast Interview.mkv");
+    }
+
+    fn episodes(path: &str) -> Vec<u32> {
+        processor().parse_media_info(Path::new(path)).episodes
+    }
+
+    #[test]
+    fn episode_ranges() {
+        assert_eq!(episodes("/in/Show.S01E01E02.1080p.WEB.mkv"), vec![1, 2]);
+        assert_eq!(episodes("/in/Show.S01E01-E03.Title.mkv"), vec![1, 2, 3]);
+        assert_eq!(episodes("/in/Show.S01E01-03.720p.HDTV.mkv"), vec![1, 2, 3]);
+        assert_eq!(episodes("/in/Show.1x01-02.mkv"), vec![1, 2]);
+    }
+
+    #[test]
+    fn number_starting_the_title_is_not_a_range_end() {
+        let info = processor().parse_media_info(Path::new("/in/Show.S01E05-10.Things.I.Hate.1080p.WEB.mkv"));
+        assert_eq!(info.episodes, vec![5]);
+        assert_eq!(info.episode_title.as_deref(), Some("10 Things I Hate"));
+        assert_eq!(episodes("/in/Show.S01E01-E40.mkv"), vec![1]);
+        assert_eq!(episodes("/in/Show.S01E05-E03.mkv"), vec![5]);
+    }
+
+    #[test]
+    fn fansub_release_is_parsed() {
+        let info = processor().parse_media_info(Path::new("/in/[Group] Show Name - 137v2 [1080p][ABCD1234].mkv"));
+        assert_eq!(info.title, "Show Name");
+        assert!(info.is_series);
+        assert_eq!((info.season, info.episodes, info.absolute), (Some(1), vec![137], Some(137)));
+        assert_eq!(info.version, Some(2));
+        assert_eq!(info.crc32.as_deref(), Some("ABCD1234"));
+        assert_eq!(info.release.group.as_deref(), Some("Group"));
+        assert_eq!(info.release.resolution.as_deref(), Some("1080p"));
+    }
+
+    #[test]
+    fn absolute_episode_maps_to_season() {
+        let config = Config {
+            root_folder: PathBuf::from("/in"),
+            output_root: PathBuf::from("/out"),
+            anime_season_lengths: HashMap::from([("Show Name".to_string(), vec![24, 24])]),
+            ..Config::default()
+        };
+        let naming = NamingTemplates::from_config(&config).unwrap();
+        let processor = Processor::new(config, naming);
+        let info = processor.parse_media_info(Path::new("/in/[Group] Show Name - 30 - The Title [720p].mkv"));
+        assert_eq!((info.season, info.episodes, info.absolute), (Some(2), vec![6], Some(30)));
+        assert_eq!(info.episode_title.as_deref(), Some("The Title"));
+        assert_eq!(info.version, None);
+    }
+
+    #[test]
+    fn season_folder_supplies_show_and_season() {
+        let processor = processor();
+        let info = processor.parse_media_info(Path::new("/in/Show.S02.1080p.WEB/E05.mkv"));
+        assert_eq!((info.title.as_str(), info.season, info.episodes), ("Show", Some(2), vec![5]));
+
+        let info = processor.parse_media_info(Path::new("/in/Show.S02.1080p.WEB/05 - Title.mkv"));
+        assert_eq!((info.title.as_str(), info.season, info.episodes), ("Show", Some(2), vec![5]));
+        assert_eq!(info.episode_title.as_deref(), Some("Title"));
+    }
+
+    #[test]
+    fn show_folder_supplies_title_above_season_folder() {
+        let info = processor().parse_media_info(Path::new("/in/The Show/Season 3/E07.mkv"));
+        assert_eq!((info.title.as_str(), info.season, info.episodes), ("The Show", Some(3), vec![7]));
+        assert!(info.sources.iter().any(|(field, source)| *field == "title" && source.contains("The Show")));
+    }
+
+    #[test]
+    fn special_keyword_in_movie_title_is_not_a_special() {
+        assert_eq!(output("/in/The.Special.2020.1080p.BluRay.x264-GRP.mkv"), "Movies/The Special (2020)/The Special (2020).mkv");
+        assert_eq!(output("/in/My.Special.Day.2015.mkv"), "Movies/My Special Day (2015)/My Special Day (2015).mkv");
+    }
+
+    #[test]
+    fn specials_keep_the_show_year() {
+        assert_eq!(
+            output("/in/Doctor.Who.2005.Christmas.Special.mkv"),
+            "TV Shows/Doctor Who (2005)/Specials/Doctor Who - S00 - Christmas Special.mkv"
+        );
+        assert_eq!(output("/in/Doctor.Who.2005.S01E01.mkv"), "TV Shows/Doctor Who (2005)/Season 01/Doctor Who - S01E01.mkv");
+        assert_eq!(output("/in/Show.OVA.2.mkv"), "TV Shows/Show/Specials/Show - S00E02 - OVA 2.mkv");
+    }
+
+    #[test]
+    fn top_level_extras_folder_holds_movies() {
+        assert_eq!(output("/in/Shorts/Some.Short.Film.2010.mkv"), "Movies/Some Short Film (2010)/Some Short Film (2010).mkv");
+        assert_eq!(
+            output("/in/Inception (2010)/Shorts/Some.Short.Film.mkv"),
+            "Movies/Inception (2010)/Shorts/Some Short Film.mkv"
+        );
+    }
+
+    #[test]
+    fn episodeless_files_are_not_counted() {
+        let mut processor = processor();
+        let videos = [
+            "/in/Show.S01E01.mkv",
+            "/in/Show.S01E02.mkv",
+            "/in/Daily.Show.2024.03.15.mkv",
+            "/in/Show.Christmas.Special.mkv",
+        ];
+        processor.register_batch(&videos.iter().map(|v| vec![PathBuf::from(v)]).collect::<Vec<_>>());
+        assert_eq!(processor.season_sizes.get(&("Show".to_string(), 1)), Some(&2));
+        assert!(processor.season_sizes.values().all(|&n| n > 0));
+    }
+
+    fn air_date(name: &str) -> Option<String> {
+        processor().parse_air_date(Path::new("/in/x.mkv"), name).and_then(|info| info.air_date)
+    }
+
+    #[test]
+    fn air_dates() {
+        assert_eq!(air_date("The.Daily.Show.2024.03.15.Guest.Name"), Some("2024-03-15".to_string()));
+        assert_eq!(air_date("The Daily Show 15-03-2024"), Some("2024-03-15".to_string()));
+        // Month first when the second number cannot be a month
+        assert_eq!(air_date("The.Daily.Show.03.15.2024"), Some("2024-03-15".to_string()));
+        assert_eq!(air_date("The.Daily.Show.2024.13.01"), None);
+        assert_eq!(air_date("The.Daily.Show.2024.03.32"), None);
+        assert_eq!(air_date("The.Daily.Show.32.13.2024"), None);
+
+        let info = processor().parse_air_date(Path::new("/in/x.mkv"), "The.Daily.Show.2024.03.15.Guest.Name").unwrap();
+        assert_eq!((info.title.as_str(), info.season, info.year.as_deref()), ("The Daily Show", Some(2024), Some("2024")));
+        assert_eq!(info.episode_title.as_deref(), Some("Guest Name"));
+    }
+}
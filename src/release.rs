@@ -0,0 +1,318 @@
+use regex::Regex;
+use once_cell::sync::Lazy;
+
+/// Characters that separate tokens in release names.
+const SEPARATORS: &[char] = &['.', ' ', '_', '-', '[', ']', '(', ')'];
+/// Separators left dangling at the end of a name once the tags are cut off.
+const NAME_TRAILER: &[char] = &['.', ' ', '_', '-', '[', '('];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Resolution,
+    Source,
+    VideoCodec,
+    Audio,
+    Hdr,
+    Edition,
+    Flag,
+    /// Only counted once a release tag has been seen, since language names also appear in titles
+    Language,
+}
+
+/// Known release tags: kind, pattern, display label and whether the tag is distinctive enough
+/// to mark the end of a title on its own. An empty label keeps the matched text.
+static TAGS: Lazy<Vec<(TagKind, Regex, &'static str, bool)>> = Lazy::new(|| {
+    let table: &[(TagKind, &str, &str, bool)] = &[
+        (TagKind::Resolution, r"\d{3,4}[pi]", "", true),
+        (TagKind::Resolution, r"4k|8k|uhd", "", true),
+        (TagKind::Resolution, r"\d{3,4}x\d{3,4}", "", true),
+        (TagKind::Source, r"web[\-\. ]?dl", "WEB-DL", true),
+        (TagKind::Source, r"web[\-\. ]?rip", "WEBRip", true),
+        (TagKind::Source, r"web", "WEB", false),
+        (TagKind::Source, r"blu[\-\. ]?ray|bdrip|brrip", "BluRay", true),
+        (TagKind::Source, r"(?:bd)?remux", "Remux", false),
+        (TagKind::Source, r"hdtv|pdtv", "HDTV", true),
+        (TagKind::Source, r"dvd[\-\. ]?rip", "DVDRip", true),
+        (TagKind::Source, r"dvd(?:r|5|9)?", "DVD", false),
+        (TagKind::Source, r"hdrip", "HDRip", true),
+        (TagKind::VideoCodec, r"x264|h[\. ]?264|avc", "H.264", true),
+        (TagKind::VideoCodec, r"x265|h[\. ]?265|hevc", "H.265", true),
+        (TagKind::VideoCodec, r"xvid|divx", "XviD", true),
+        (TagKind::VideoCodec, r"av1", "AV1", false),
+        (TagKind::VideoCodec, r"vp9", "VP9", false),
+        (TagKind::Audio, r"true[\-\. ]?hd", "TrueHD", true),
+        (TagKind::Audio, r"atmos", "Atmos", false),
+        (TagKind::Audio, r"dts[\-\. ]?hd(?:[\-\. ]?ma)?", "DTS-HD MA", true),
+        (TagKind::Audio, r"dts[\-\. ]?x", "DTS:X", false),
+        (TagKind::Audio, r"dts", "DTS", false),
+        (TagKind::Audio, r"ddp|dd\+|e[\-\. ]?ac[\-\. ]?3", "DD+", false),
+        (TagKind::Audio, r"dd|ac[\-\. ]?3", "DD", false),
+        (TagKind::Audio, r"aac", "AAC", false),
+        (TagKind::Audio, r"flac", "FLAC", false),
+        (TagKind::Audio, r"opus", "Opus", false),
+        (TagKind::Audio, r"mp3", "MP3", false),
+        (TagKind::Audio, r"l?pcm", "PCM", false),
+        (TagKind::Hdr, r"hdr10(?:\+|plus)", "HDR10+", true),
+        (TagKind::Hdr, r"hdr10", "HDR10", false),
+        (TagKind::Hdr, r"hdr", "HDR", false),
+        (TagKind::Hdr, r"dv|dovi|dolby[\. ]?vision", "DV", false),
+        (TagKind::Hdr, r"hlg", "HLG", false),
+        (TagKind::Edition, r"director'?s[\. _]cut", "Director's Cut", false),
+        (TagKind::Edition, r"extended(?:[\. _](?:cut|edition))?", "Extended", false),
+        (TagKind::Edition, r"theatrical(?:[\. _]cut)?", "Theatrical", false),
+        (TagKind::Edition, r"unrated", "Unrated", false),
+        (TagKind::Edition, r"uncut", "Uncut", false),
+        (TagKind::Edition, r"remastered", "Remastered", false),
+        (TagKind::Edition, r"imax", "IMAX", false),
+        (TagKind::Edition, r"criterion", "Criterion", false),
+        (TagKind::Flag, r"proper|repack|rerip|internal", "", false),
+        (TagKind::Language, r"multi|dual(?:[\. ]audio)?", "Multi", false),
+        (TagKind::Language, r"(?:true)?french|vff|vostfr", "French", false),
+        (TagKind::Language, r"german", "German", false),
+        (TagKind::Language, r"italian", "Italian", false),
+        (TagKind::Language, r"spanish|latino", "Spanish", false),
+        (TagKind::Language, r"russian", "Russian", false),
+        (TagKind::Language, r"japanese", "Japanese", false),
+        (TagKind::Language, r"korean", "Korean", false),
+        (TagKind::Language, r"hindi", "Hindi", false),
+        (TagKind::Language, r"finnish", "Finnish", false),
+        (TagKind::Language, r"swedish", "Swedish", false),
+        (TagKind::Language, r"nordic", "Nordic", false),
+    ];
+
+    table.iter()
+        .map(|&(kind, pattern, label, strong)| (kind, Regex::new(&format!(r"(?i)^(?:{})", pattern)).unwrap(), label, strong))
+        .collect()
+});
+
+/// Channel layout directly following an audio tag, e.g. `5.1` in `DDP5.1`.
+static CHANNELS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\. ]?([1-9])[\. ]([01])").unwrap());
+
+/// Release group after the last dash, optionally followed by a bracketed tag: `x264-GRP[rarbg]`.
+static GROUP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"-([A-Za-z0-9]+)(?:\[[^\]]*\])?$").unwrap());
+
+/// Technical details recognized in a release name.
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseTags {
+    pub resolution: Option<String>,
+    pub source: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio: Option<String>,
+    pub hdr: Vec<String>,
+    pub edition: Option<String>,
+    pub languages: Vec<String>,
+    pub group: Option<String>,
+    /// PROPER, REPACK and similar re-release markers
+    pub flags: Vec<String>,
+}
+
+impl ReleaseTags {
+    pub fn is_empty(&self) -> bool {
+        self.summary().is_empty()
+    }
+
+    /// The recognized tags in display order, e.g. "1080p · BluRay · H.264 · DTS 5.1 · GRP".
+    pub fn summary(&self) -> String {
+        [&self.resolution, &self.source, &self.video_codec, &self.audio]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(self.hdr.iter().cloned())
+            .chain(self.edition.clone())
+            .chain(self.languages.iter().cloned())
+            .chain(self.flags.iter().cloned())
+            .chain(self.group.clone())
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
+    fn record(&mut self, kind: TagKind, value: String) {
+        let slot = match kind {
+            TagKind::Resolution => &mut self.resolution,
+            TagKind::Source => &mut self.source,
+            TagKind::VideoCodec => &mut self.video_codec,
+            TagKind::Audio => &mut self.audio,
+            TagKind::Edition => &mut self.edition,
+            TagKind::Hdr => return push_unique(&mut self.hdr, value),
+            TagKind::Language => return push_unique(&mut self.languages, value),
+            TagKind::Flag => return push_unique(&mut self.flags, value),
+        };
+        slot.get_or_insert(value);
+    }
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+/// A recognized tag within a release name.
+struct Tag {
+    kind: TagKind,
+    value: String,
+    strong: bool,
+    start: usize,
+    end: usize,
+}
+
+/// Matches a release tag starting exactly at `rest`, returning its kind, label, strength and length.
+fn match_tag(rest: &str) -> Option<(TagKind, String, bool, usize)> {
+    TAGS.iter().find_map(|&(kind, ref re, label, strong)| {
+        let m = re.find(rest)?;
+        let mut end = m.end();
+        let mut value = if label.is_empty() { m.as_str().to_string() } else { label.to_string() };
+
+        if kind == TagKind::Audio {
+            if let Some(caps) = CHANNELS_RE.captures(&rest[end..]) {
+                value = format!("{} {}.{}", value, &caps[1], &caps[2]);
+                end += caps[0].len();
+            }
+        }
+
+        // The tag must end at a separator, otherwise "web" would match the start of "Webster"
+        (end == rest.len() || rest[end..].starts_with(SEPARATORS)).then(|| {
+            let value = match kind {
+                TagKind::Resolution => value.to_lowercase(),
+                TagKind::Flag => value.to_uppercase(),
+                _ => value,
+            };
+            (kind, value, strong, end)
+        })
+    })
+}
+
+/// Whether `text` opens with a release tag: `1080p.WEB` does, `Webster` does not.
+pub fn starts_with_tag(text: &str) -> bool {
+    match_tag(text).is_some()
+}
+
+/// Finds the tags at token starts, skipping the first token so a name is never empty.
+fn find_tags(stem: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    let mut pos = stem.find(SEPARATORS).unwrap_or(stem.len());
+
+    while pos < stem.len() {
+        let at_token_start = stem[..pos].ends_with(SEPARATORS);
+        if at_token_start {
+            if let Some((kind, value, strong, len)) = match_tag(&stem[pos..]) {
+                tags.push(Tag { kind, value, strong, start: pos, end: pos + len });
+                pos += len;
+                continue;
+            }
+        }
+        pos += stem[pos..].chars().next().map_or(1, |c| c.len_utf8());
+    }
+
+    tags
+}
+
+/// True if only separators, and release years when `years` is set, lie in `between`.
+fn only_separators_between(between: &str, years: bool) -> bool {
+    between.split(SEPARATORS).all(|token| {
+        token.is_empty()
+            || (years && token.len() == 4 && (token.starts_with("19") || token.starts_with("20")) && token.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// Splits a file stem into the name part and the release tags in it.
+///
+/// The name ends at the first distinctive tag (resolution, source, codec), so
+/// `Movie.Name.1080p.BluRay.x264-GRP` yields `Movie.Name` plus resolution, source, codec and
+/// group. Everything after that point is release information. Ambiguous tags such as `WEB` or
+/// `DTS` are only taken from the name when they directly precede it, so `The.Web.2019.WEB.1080p`
+/// keeps its title. Editions may also sit before a year or at the very end of a name. Language
+/// tags only count after the name.
+pub fn split_release(stem: &str) -> (String, ReleaseTags) {
+    let found = find_tags(stem);
+    let mut tags = ReleaseTags::default();
+
+    let cut = found.iter().position(|t| t.strong);
+    let cut_at = cut.map_or(stem.len(), |i| found[i].start);
+
+    // Walk back over the ambiguous tags directly before the cut
+    let mut removed = Vec::new();
+    let mut boundary = cut_at;
+    for tag in found[..cut.unwrap_or(found.len())].iter().rev() {
+        let allowed = tag.kind != TagKind::Language && (cut.is_some() || tag.kind == TagKind::Edition);
+        if !allowed || !only_separators_between(&stem[tag.end..boundary], tag.kind == TagKind::Edition) {
+            break;
+        }
+        removed.push(tag);
+        boundary = tag.start;
+    }
+
+    let mut name = String::new();
+    let mut pos = 0;
+    for tag in removed.iter().rev() {
+        name.push_str(&stem[pos..tag.start]);
+        pos = tag.end;
+        tags.record(tag.kind, tag.value.clone());
+    }
+    name.push_str(&stem[pos..cut_at]);
+
+    let Some(cut) = cut else { return (name.trim_end_matches(NAME_TRAILER).to_string(), tags) };
+
+    for tag in &found[cut..] {
+        tags.record(tag.kind, tag.value.clone());
+    }
+
+    let last_tag_end = found.last().map_or(0, |t| t.end);
+    if let Some(caps) = GROUP_RE.captures(stem) {
+        let group = caps.get(1).unwrap();
+        if group.start() >= last_tag_end {
+            tags.group = Some(group.as_str().to_string());
+        }
+    }
+
+    (name.trim_end_matches(NAME_TRAILER).to_string(), tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_name_from_tags() {
+        let (name, tags) = split_release("Movie.Name.1080p.BluRay.x264-GRP");
+        assert_eq!(name, "Movie.Name");
+        assert_eq!(tags.resolution.as_deref(), Some("1080p"));
+        assert_eq!(tags.source.as_deref(), Some("BluRay"));
+        assert_eq!(tags.video_codec.as_deref(), Some("H.264"));
+        assert_eq!(tags.group.as_deref(), Some("GRP"));
+        assert_eq!(tags.summary(), "1080p · BluRay · H.264 · GRP");
+    }
+
+    #[test]
+    fn weak_tags_only_count_next_to_the_release() {
+        assert_eq!(split_release("The.Web.2019").0, "The.Web.2019");
+        let (name, tags) = split_release("The.Web.2019.WEB.1080p");
+        assert_eq!(name, "The.Web.2019");
+        assert_eq!(tags.source.as_deref(), Some("WEB"));
+        assert_eq!(split_release("Webster.S01E01").0, "Webster.S01E01");
+    }
+
+    #[test]
+    fn editions_audio_and_hdr() {
+        let (name, tags) = split_release("Movie.2010.Directors.Cut.2160p.UHD.BluRay.REMUX.HDR10+.DV.TrueHD.Atmos.7.1-GRP");
+        assert_eq!(name, "Movie.2010");
+        assert_eq!(tags.edition.as_deref(), Some("Director's Cut"));
+        assert_eq!(tags.audio.as_deref(), Some("TrueHD"));
+        let (name, tags2) = split_release("Movie.2010.Extended.1080p.BluRay.DDP5.1.x265-GRP");
+        assert_eq!(name, "Movie.2010");
+        assert_eq!(tags2.edition.as_deref(), Some("Extended"));
+        assert_eq!(tags2.audio.as_deref(), Some("DD+ 5.1"));
+        assert_eq!(tags2.video_codec.as_deref(), Some("H.265"));
+        assert_eq!(tags.hdr, vec!["HDR10+", "DV"]);
+        assert_eq!(split_release("Movie.2010.Unrated").0, "Movie.2010");
+    }
+
+    #[test]
+    fn languages_only_after_the_name() {
+        let (name, tags) = split_release("French.Kiss.1995.1080p.FRENCH.WEB-DL");
+        assert_eq!(name, "French.Kiss.1995");
+        assert_eq!(tags.languages, vec!["French"]);
+        assert_eq!(tags.source.as_deref(), Some("WEB-DL"));
+        assert!(split_release("French.Kiss.1995").1.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, bail, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use crate::config::{Config, RouteRule};
+use crate::lang::lookup_language;
+use crate::naming::NamingTemplates;
+
+/// A routing rule ready for matching.
+pub struct Route {
+    pub name: String,
+    /// Library root the rule's templates are rendered under
+    pub root: PathBuf,
+    pub naming: NamingTemplates,
+    path_glob: Option<GlobMatcher>,
+    title: Option<Regex>,
+    /// ISO 639-2 code
+    language: Option<String>,
+    category: Option<String>,
+}
+
+/// What a file offers to the routing conditions.
+pub struct RouteInput<'a> {
+    /// Source path relative to `root_folder`
+    pub relative_path: &'a Path,
+    pub title: &'a str,
+    pub category: Option<&'a str>,
+    /// ISO 639-2 codes of the file's languages
+    pub languages: &'a [String],
+}
+
+impl Route {
+    fn compile(rule: &RouteRule, config: &Config) -> Result<Self> {
+        if rule.name.trim().is_empty() {
+            bail!("every route needs a name");
+        }
+
+        let path_glob = match rule.path_glob.as_str() {
+            "" => None,
+            pattern => Some(
+                GlobBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("route \"{}\": invalid path_glob", rule.name))?
+                    .compile_matcher(),
+            ),
+        };
+        let title = match rule.title.as_str() {
+            "" => None,
+            pattern => Some(
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("route \"{}\": invalid title pattern", rule.name))?,
+            ),
+        };
+        let language = match rule.language.as_str() {
+            "" => None,
+            lang => Some(
+                lookup_language(lang)
+                    .ok_or_else(|| anyhow!("route \"{}\": unknown language \"{}\"", rule.name, lang))?
+                    .iso,
+            ),
+        };
+
+        let mut naming = match rule.naming_preset.as_str() {
+            "" => NamingTemplates::from_config(config)?,
+            preset => NamingTemplates::preset(preset)
+                .ok_or_else(|| anyhow!("route \"{}\": unknown naming preset \"{}\"", rule.name, preset))?,
+        };
+        let overrides = [
+            (&mut naming.movie_folder, &rule.movie_folder_template),
+            (&mut naming.movie_file, &rule.movie_file_template),
+            (&mut naming.series_folder, &rule.series_folder_template),
+            (&mut naming.episode_file, &rule.episode_file_template),
+            (&mut naming.daily_folder, &rule.daily_folder_template),
+            (&mut naming.daily_file, &rule.daily_file_template),
+            (&mut naming.edition, &rule.edition_template),
+        ];
+        for (template, custom) in overrides {
+            if let Some(custom) = custom {
+                *template = custom.clone();
+            }
+        }
+
+        Ok(Self {
+            name: rule.name.clone(),
+            root: rule.root.clone().unwrap_or_else(|| config.output_root.join(&rule.name)),
+            naming,
+            path_glob,
+            title,
+            language,
+            category: (!rule.category.is_empty()).then(|| rule.category.to_lowercase()),
+        })
+    }
+
+    /// Whether the rule looks at languages, which may take an `mkvmerge -J` call to find out.
+    pub fn needs_languages(&self) -> bool {
+        self.language.is_some()
+    }
+
+    pub fn matches(&self, input: &RouteInput) -> bool {
+        self.path_glob.as_ref().is_none_or(|g| g.is_match(input.relative_path))
+            && self.title.as_ref().is_none_or(|re| re.is_match(input.title))
+            && self.category.as_ref().is_none_or(|c| input.category.is_some_and(|ic| ic.to_lowercase() == *c))
+            && self.language.as_ref().is_none_or(|l| input.languages.contains(l))
+    }
+}
+
+/// Compiles the `[[routes]]` of the config in order. Invalid globs, regexes, languages or
+/// presets are errors, since a broken rule would silently file content in the wrong library.
+pub fn compile_routes(config: &Config) -> Result<Vec<Route>> {
+    config.routes.iter().map(|rule| Route::compile(rule, config)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str) -> RouteRule {
+        RouteRule { name: name.to_string(), ..RouteRule::default() }
+    }
+
+    fn routes(rules: Vec<RouteRule>) -> Vec<Route> {
+        let config = Config { output_root: PathBuf::from("/out"), routes: rules, ..Config::default() };
+        compile_routes(&config).unwrap()
+    }
+
+    fn input<'a>(path: &'a str, title: &'a str, category: Option<&'a str>, languages: &'a [String]) -> RouteInput<'a> {
+        RouteInput { relative_path: Path::new(path), title, category, languages }
+    }
+
+    fn first_match<'a>(routes: &'a [Route], input: &RouteInput) -> Option<&'a str> {
+        routes.iter().find(|r| r.matches(input)).map(|r| r.name.as_str())
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let routes = routes(vec![
+            RouteRule { path_glob: "**/Anime/**".into(), ..rule("Anime") },
+            RouteRule { category: "kids".into(), ..rule("Kids") },
+            rule("Everything"),
+        ]);
+        let no_langs: &[String] = &[];
+        assert_eq!(first_match(&routes, &input("Anime/Show/Show - 01.mkv", "Show", Some("Kids"), no_langs)), Some("Anime"));
+        assert_eq!(first_match(&routes, &input("Cartoons/Show.S01E01.mkv", "Show", Some("KIDS"), no_langs)), Some("Kids"));
+        assert_eq!(first_match(&routes, &input("Movie.mkv", "Movie", None, no_langs)), Some("Everything"));
+        assert_eq!(routes[0].root, PathBuf::from("/out/Anime"));
+    }
+
+    #[test]
+    fn all_conditions_must_match() {
+        let routes = routes(vec![RouteRule {
+            title: "^doctor who$".into(),
+            language: "English".into(),
+            root: Some(PathBuf::from("/library/british")),
+            ..rule("British")
+        }]);
+        let english = ["eng".to_string()];
+        let japanese = ["jpn".to_string()];
+        assert!(routes[0].needs_languages());
+        assert!(routes[0].matches(&input("Doctor.Who.S01E01.mkv", "Doctor Who", None, &english)));
+        assert!(!routes[0].matches(&input("Doctor.Who.S01E01.mkv", "Doctor Who", None, &japanese)));
+        assert!(!routes[0].matches(&input("Doctor.Who.Confidential.mkv", "Doctor Who Confidential", None, &english)));
+        assert_eq!(routes[0].root, PathBuf::from("/library/british"));
+    }
+
+    #[test]
+    fn rule_templates_override_the_preset() {
+        let routes = routes(vec![RouteRule {
+            naming_preset: "kodi".into(),
+            movie_file_template: Some("{title}.{ext}".into()),
+            ..rule("Kodi")
+        }]);
+        assert_eq!(routes[0].naming.movie_file, "{title}.{ext}");
+        assert_eq!(routes[0].naming.series_folder, NamingTemplates::preset("kodi").unwrap().series_folder);
+    }
+
+    #[test]
+    fn invalid_rules_are_errors() {
+        let config = |rule: RouteRule| Config { routes: vec![rule], ..Config::default() };
+        assert!(compile_routes(&config(rule(" "))).is_err());
+        assert!(compile_routes(&config(RouteRule { path_glob: "[".into(), ..rule("A") })).is_err());
+        assert!(compile_routes(&config(RouteRule { title: "(".into(), ..rule("A") })).is_err());
+        assert!(compile_routes(&config(RouteRule { language: "Klingonese".into(), ..rule("A") })).is_err());
+        assert!(compile_routes(&config(RouteRule { naming_preset: "emby".into(), ..rule("A") })).is_err());
+    }
+}
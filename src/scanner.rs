@@ -1,86 +1,513 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use regex::Regex;
 use once_cell::sync::Lazy;
 use crate::config::Config;
+use crate::lang::is_track_only_name;
+use crate::release::split_release;
 
 static SXXEXX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)s(\d+)e(\d+)").unwrap());
+/// Part marker at the end of a name: `CD1`, `Disc 2`, `[Disk 1]`. `Part 1` is left alone, it
+/// is as likely to be a title (`Deathly.Hallows.Part.1`) as a split file.
+static PART_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^(.*?)[\. _\-\[(]+(?:cd|disc|disk)[\. _\-]?(\d{1,2})[\])]?$").unwrap());
+static CHAPTERS_SUFFIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)[\. \-_]*chapters?$").unwrap());
 
 /// Normalizes text for fingerprinting: lowercase alphanumeric only.
-fn get_fingerprint(text: &str) -> String {
+pub fn get_fingerprint(text: &str) -> String {
     text.chars()
         .filter(|c| c.is_alphanumeric())
         .map(|c| c.to_lowercase().to_string())
         .collect()
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct FoundAssets {
     pub subtitles: Vec<PathBuf>,
     pub audios: Vec<PathBuf>,
+    pub chapters: Vec<PathBuf>,
+    /// Fonts from `Fonts/`-style folders. Shared between videos, so never deleted.
+    pub fonts: Vec<PathBuf>,
+    pub covers: Vec<CoverArt>,
+    /// Files that belong to a muxed asset without being passed to mkvmerge (e.g. the `.sub` of a VobSub pair).
+    pub companions: Vec<PathBuf>,
 }
 
-/// Scans the directory of the given video file for matching subtitles and audio tracks.
-pub fn find_matching_assets(video_path: &Path, config: &Config) -> FoundAssets {
-    let mut assets = FoundAssets::default();
+impl FoundAssets {
+    /// Sorts an asset into subtitles, audios or chapters by its name and extension.
+    pub fn push(&mut self, path: PathBuf, config: &Config) {
+        if is_chapter_file(&path, config) {
+            self.chapters.push(path);
+            return;
+        }
+        match asset_extension(&path) {
+            Some(ext) if config.ext_sub.contains(&ext) => self.subtitles.push(path),
+            Some(ext) if config.ext_audio.contains(&ext) => self.audios.push(path),
+            _ => {}
+        }
+    }
+}
 
-    let Some(parent) = video_path.parent() else { return assets; };
-    let Some(video_stem) = video_path.file_stem().and_then(|s| s.to_str()) else { return assets; };
+/// A poster image to attach as Matroska cover art.
+#[derive(Debug, Clone)]
+pub struct CoverArt {
+    pub path: PathBuf,
+    /// Attach as `small_cover` instead of `cover`.
+    pub small: bool,
+    /// Named after the video (`<stem>-poster.jpg`) rather than shared by the folder (`poster.jpg`).
+    pub per_video: bool,
+}
 
-    let video_fingerprint = get_fingerprint(video_stem);
-    if video_fingerprint.is_empty() { return assets; }
+/// File stems recognized as folder-level cover art, Kodi/Jellyfin style.
+const FOLDER_COVER_STEMS: [&str; 3] = ["poster", "cover", "folder"];
+const FOLDER_SMALL_COVER_STEMS: [&str; 2] = ["small_cover", "poster-small"];
+/// Suffixes for per-video cover art, e.g. `<stem>-poster.jpg`.
+const VIDEO_COVER_SUFFIXES: [&str; 2] = ["-poster", "-cover"];
+const VIDEO_SMALL_COVER_SUFFIXES: [&str; 2] = ["-small_cover", "-poster-small"];
 
-    // Directories to search: current folder and potential 'Subs' or 'Subtitles' subfolders
-    let mut search_dirs = vec![parent.to_path_buf()];
-    
-    let subs_subfolder = parent.join("Subs");
-    if subs_subfolder.is_dir() { search_dirs.push(subs_subfolder); }
-    
-    let subtitles_subfolder = parent.join("Subtitles");
-    if subtitles_subfolder.is_dir() { search_dirs.push(subtitles_subfolder); }
+/// An asset that matched a video, together with how strongly it matched.
+#[derive(Debug, Clone)]
+pub struct AssetMatch {
+    pub path: PathBuf,
+    pub score: u32,
+    pub companions: Vec<PathBuf>,
+}
 
-    for dir in search_dirs {
-        let entries = match std::fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+/// Returns the lowercase, dot-prefixed extension of a file (e.g. `.srt`).
+fn asset_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e.to_ascii_lowercase()))
+}
 
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path == *video_path || !path.is_file() {
-                continue;
+/// Whether the file is a chapter file (`Movie.chapters.xml`, `chapters.txt`).
+pub fn is_chapter_file(path: &Path, config: &Config) -> bool {
+    let Some(ext) = asset_extension(path) else { return false };
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else { return false };
+    config.ext_chapters.contains(&ext) && CHAPTERS_SUFFIX_RE.is_match(stem)
+}
+
+/// Whether the file is a font that can be attached for ASS/SSA subtitles.
+pub fn is_font_file(path: &Path, config: &Config) -> bool {
+    asset_extension(path).is_some_and(|ext| config.ext_fonts.contains(&ext))
+}
+
+/// Whether the file is a cover image by name (`poster.jpg`, `<stem>-poster.png`, ...).
+pub fn is_cover_file(path: &Path, config: &Config) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_lowercase()) else { return false };
+    asset_extension(path).is_some_and(|ext| config.ext_images.contains(&ext))
+        && (FOLDER_COVER_STEMS.iter().chain(&FOLDER_SMALL_COVER_STEMS).any(|c| stem == *c)
+            || VIDEO_COVER_SUFFIXES.iter().chain(&VIDEO_SMALL_COVER_SUFFIXES).any(|c| stem.ends_with(c)))
+}
+
+/// Whether the file is a subtitle, audio track or chapter file according to the config.
+pub fn is_asset(path: &Path, config: &Config) -> bool {
+    match asset_extension(path) {
+        Some(ext) => config.ext_sub.contains(&ext) || config.ext_audio.contains(&ext) || is_chapter_file(path, config),
+        None => false,
+    }
+}
+
+/// Extracts the (season, episode) pair from an SxxExx tag.
+fn episode_key(text: &str) -> Option<(u32, u32)> {
+    let caps = SXXEXX_RE.captures(text)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()?))
+}
+
+/// Scores how well an asset name matches a video name. Higher is better, `None` means no match.
+///
+/// An identical fingerprint scores 100. When both names carry an SxxExx tag they must agree:
+/// a shared tag scores 75 to 99, a different one never matches however similar the names are.
+/// Otherwise containment in either direction scores 50 to 74. Either way the score grows with
+/// how little extra text the longer name carries.
+fn match_score(video_fingerprint: &str, video_episode: Option<(u32, u32)>, item: &IndexedAsset) -> Option<u32> {
+    let item_fingerprint = item.fingerprint.as_str();
+
+    if item_fingerprint == video_fingerprint {
+        return Some(100);
+    }
+
+    // Match if one fingerprint is contained in the other, or if they share a common SxxExx
+    // This handles cases where video has more tags than sub, or vice versa.
+    let contained = item_fingerprint.contains(video_fingerprint) || video_fingerprint.contains(item_fingerprint);
+    let shorter = item_fingerprint.len().min(video_fingerprint.len()) as u32;
+    let longer = item_fingerprint.len().max(video_fingerprint.len()).max(1) as u32;
+    let closeness = if contained { 24 * shorter / longer } else { 0 };
+
+    match (video_episode, item.episode) {
+        (Some(video), Some(asset)) if video != asset => None,
+        (Some(_), Some(_)) => Some(75 + closeness),
+        _ if contained => Some(50 + closeness),
+        _ => None,
+    }
+}
+
+struct IndexedAsset {
+    path: PathBuf,
+    companions: Vec<PathBuf>,
+    fingerprint: String,
+    episode: Option<(u32, u32)>,
+}
+
+/// All assets living directly in one directory, keyed for fast lookup.
+#[derive(Default)]
+struct DirIndex {
+    assets: Vec<IndexedAsset>,
+    fonts: Vec<PathBuf>,
+    covers: Vec<PathBuf>,
+    by_fingerprint: BTreeMap<String, Vec<usize>>,
+    /// Every suffix of every fingerprint, so assets containing the video name anywhere are found
+    by_suffix: BTreeMap<String, Vec<usize>>,
+    by_episode: HashMap<(u32, u32), Vec<usize>>,
+}
+
+impl DirIndex {
+    fn insert(&mut self, asset: IndexedAsset) {
+        let idx = self.assets.len();
+        self.by_fingerprint.entry(asset.fingerprint.clone()).or_default().push(idx);
+        for (i, _) in asset.fingerprint.char_indices() {
+            self.by_suffix.entry(asset.fingerprint[i..].to_string()).or_default().push(idx);
+        }
+        if let Some(key) = asset.episode {
+            self.by_episode.entry(key).or_default().push(idx);
+        }
+        self.assets.push(asset);
+    }
+
+    /// Collects candidates whose fingerprint contains or is contained in the video fingerprint,
+    /// plus everything sharing its SxxExx tag. These are exactly the assets `match_score` can accept.
+    fn candidates(&self, video_fingerprint: &str, video_episode: Option<(u32, u32)>) -> BTreeSet<usize> {
+        let mut found = BTreeSet::new();
+
+        // Asset carries extra tags around the video name (e.g. `.en`, `[Group]`)
+        for (suffix, ids) in self.by_suffix.range(video_fingerprint.to_string()..) {
+            if !suffix.starts_with(video_fingerprint) { break; }
+            found.extend(ids);
+        }
+
+        // Video carries extra tags around the asset name (e.g. `[Group] Show - 01 [1080p]`)
+        let bounds: Vec<usize> = video_fingerprint.char_indices().map(|(i, _)| i).chain([video_fingerprint.len()]).collect();
+        for (n, &start) in bounds.iter().enumerate() {
+            for &end in &bounds[n..] {
+                if let Some(ids) = self.by_fingerprint.get(&video_fingerprint[start..end]) {
+                    found.extend(ids);
+                }
             }
+        }
 
-            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
-            let Some(item_stem) = path.file_stem().and_then(|s| s.to_str()) else { continue };
-            
-            let Some(ext) = path.extension().and_then(|e| e.to_str()) else { continue };
-            let ext = format!(".{}", ext.to_ascii_lowercase());
+        if let Some(ids) = video_episode.and_then(|key| self.by_episode.get(&key)) {
+            found.extend(ids);
+        }
+
+        found
+    }
+}
 
-            if !config.ext_sub.contains(&ext) && !config.ext_audio.contains(&ext) {
+/// Subtitle and audio files found during the library scan, indexed per directory.
+///
+/// Built once before processing and shared read-only by all workers, so matching a video
+/// is a handful of map lookups instead of a directory listing per video.
+#[derive(Default)]
+pub struct AssetIndex {
+    dirs: BTreeMap<PathBuf, DirIndex>,
+    /// `.sub` files without an `.idx` (broken VobSub or MicroDVD text, neither of which mkvmerge can mux).
+    unpaired: Vec<PathBuf>,
+}
+
+impl AssetIndex {
+    pub fn build(asset_files: &[PathBuf], config: &Config) -> Self {
+        let mut index = Self::default();
+
+        // A VobSub is an `.idx` + `.sub` pair; only the `.idx` is indexed, the `.sub` rides along
+        let vobsub_bases: HashSet<PathBuf> = asset_files.iter()
+            .filter(|p| asset_extension(p).as_deref() == Some(".idx"))
+            .map(|p| p.with_extension(""))
+            .collect();
+        let mut vobsub_companions: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for path in asset_files.iter().filter(|p| asset_extension(p).as_deref() == Some(".sub")) {
+            let base = path.with_extension("");
+            if vobsub_bases.contains(&base) {
+                vobsub_companions.entry(base).or_default().push(path.clone());
+            } else {
+                index.unpaired.push(path.clone());
+            }
+        }
+
+        for path in asset_files {
+            if asset_extension(path).as_deref() == Some(".sub") { continue; }
+
+            if is_font_file(path, config) || is_cover_file(path, config) {
+                if let Some(parent) = path.parent() {
+                    let dir = index.dirs.entry(parent.to_path_buf()).or_default();
+                    if is_font_file(path, config) {
+                        dir.fonts.push(path.clone());
+                    } else {
+                        dir.covers.push(path.clone());
+                    }
+                }
                 continue;
             }
 
-            let item_fingerprint = get_fingerprint(item_stem);
-            
-            // Match if one fingerprint is contained in the other, or if they share a common SxxExx
-            // This handles cases where video has more tags than sub, or vice versa.
-            let is_match = if item_fingerprint.contains(&video_fingerprint) || video_fingerprint.contains(&item_fingerprint) {
-                true
-            } else if let (Some(v_cap), Some(i_cap)) = (SXXEXX_RE.captures(video_stem), SXXEXX_RE.captures(name)) {
-                v_cap[1] == i_cap[1] && v_cap[2] == i_cap[2]
+            let (Some(parent), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else { continue };
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+
+            // `Movie.chapters.xml` is identified by `Movie`, a bare `chapters.txt` by its folder
+            let stem = if is_chapter_file(path, config) { CHAPTERS_SUFFIX_RE.replace(stem, "") } else { stem.into() };
+            let stem = stem.as_ref();
+
+            // `Subs/Show.S01E01/2_English.srt`: the folder identifies the episode, the file only the track.
+            // Language folders (`Subs/Show.S01E01/Finnish/`) are skipped on the way up.
+            let (identity_name, identity_stem) = if stem.is_empty() || is_track_only_name(stem) {
+                let folder = parent.ancestors()
+                    .filter_map(|a| a.file_name().and_then(|n| n.to_str()))
+                    .find(|n| !is_track_only_name(n))
+                    .unwrap_or(stem);
+                (folder, folder)
             } else {
-                false
+                (name, stem)
             };
 
-            if is_match {
-                if config.ext_sub.contains(&ext) {
-                    assets.subtitles.push(path);
-                } else if config.ext_audio.contains(&ext) {
-                    assets.audios.push(path);
+            let fingerprint = get_fingerprint(identity_stem);
+            if fingerprint.is_empty() { continue; }
+
+            index.dirs.entry(parent.to_path_buf()).or_default().insert(IndexedAsset {
+                path: path.clone(),
+                companions: vobsub_companions.remove(&path.with_extension("")).unwrap_or_default(),
+                fingerprint,
+                episode: episode_key(identity_name),
+            });
+        }
+        index
+    }
+
+    /// Splits configured folder names like `Extras/Subs` into lowercase path components.
+    fn folder_patterns(folders: &[String]) -> Vec<Vec<String>> {
+        folders.iter()
+            .map(|p| p.split(['/', '\\'])
+                .filter(|c| !c.is_empty())
+                .map(|c| c.to_lowercase())
+                .collect::<Vec<_>>())
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+
+    /// Every indexed asset plus unpaired `.sub` files, in no particular order.
+    pub fn all_assets(&self) -> impl Iterator<Item = &Path> {
+        self.dirs.values()
+            .flat_map(|d| d.assets.iter().map(|a| a.path.as_path()))
+            .chain(self.unpaired.iter().map(|p| p.as_path()))
+    }
+
+    /// Folders below `base` whose relative path starts with one of the configured asset
+    /// folder patterns, compared case-insensitively (so `subs` also finds `Subs/Show.S01E01/`).
+    fn pattern_dirs<'a>(&'a self, base: &'a Path, patterns: &'a [Vec<String>]) -> impl Iterator<Item = &'a Path> + 'a {
+        self.dirs.range(base.to_path_buf()..)
+            .take_while(move |(path, _)| path.starts_with(base))
+            .map(|(path, _)| path.as_path())
+            .filter(move |path| {
+                let rel: Vec<String> = path.strip_prefix(base).unwrap_or(path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_lowercase())
+                    .collect();
+                patterns.iter().any(|p| rel.len() >= p.len() && rel[..p.len()] == p[..])
+            })
+    }
+
+    /// Folders to search for a video in `parent`: matching pattern folders below it, below its
+    /// parent (siblings) and below shared ancestors. With `include_plain` the video's own folder
+    /// and the shared ancestors themselves are searched too.
+    fn search_dirs<'a>(&'a self, parent: &'a Path, patterns: &'a [Vec<String>], include_plain: bool, config: &Config) -> BTreeSet<&'a Path> {
+        let mut dirs: BTreeSet<&Path> = BTreeSet::new();
+        if include_plain {
+            dirs.insert(parent);
+        }
+        dirs.extend(self.pattern_dirs(parent, patterns));
+
+        if config.search_sibling_folders {
+            if let Some(grandparent) = parent.parent() {
+                dirs.extend(self.pattern_dirs(grandparent, patterns));
+            }
+        }
+
+        for ancestor in parent.ancestors().skip(1).take(config.shared_asset_depth) {
+            if include_plain {
+                dirs.insert(ancestor);
+            }
+            dirs.extend(self.pattern_dirs(ancestor, patterns));
+        }
+
+        dirs
+    }
+
+    /// Fonts available to a video from the configured font folders.
+    pub fn find_fonts(&self, video_path: &Path, config: &Config) -> Vec<PathBuf> {
+        let Some(parent) = video_path.parent() else { return Vec::new() };
+        let patterns = Self::folder_patterns(&config.font_folders);
+
+        self.search_dirs(parent, &patterns, false, config)
+            .into_iter()
+            .filter_map(|d| self.dirs.get(d))
+            .flat_map(|d| d.fonts.iter().cloned())
+            .collect()
+    }
+
+    /// Finds the cover and small cover for a video. Images named after the video win over
+    /// folder images, which are looked up in the video's folder and then its shared ancestors.
+    pub fn find_covers(&self, video_path: &Path, config: &Config) -> Vec<CoverArt> {
+        let (Some(parent), Some(video_stem)) = (video_path.parent(), video_path.file_stem().and_then(|s| s.to_str())) else { return Vec::new() };
+        let video_stem = video_stem.to_lowercase();
+
+        let stem_of = |p: &PathBuf| p.file_stem().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
+        let find = |suffixes: &[&str], stems: &[&str], small: bool| -> Option<CoverArt> {
+            let own = self.dirs.get(parent).into_iter()
+                .flat_map(|d| &d.covers)
+                .find(|p| suffixes.iter().any(|s| stem_of(p) == format!("{}{}", video_stem, s)));
+            if let Some(path) = own {
+                return Some(CoverArt { path: path.clone(), small, per_video: true });
+            }
+
+            parent.ancestors()
+                .take(config.shared_asset_depth + 1)
+                .filter_map(|d| self.dirs.get(d))
+                .find_map(|d| d.covers.iter().find(|p| stems.contains(&stem_of(p).as_str())))
+                .map(|path| CoverArt { path: path.clone(), small, per_video: false })
+        };
+
+        find(&VIDEO_COVER_SUFFIXES, &FOLDER_COVER_STEMS, false)
+            .into_iter()
+            .chain(find(&VIDEO_SMALL_COVER_SUFFIXES, &FOLDER_SMALL_COVER_STEMS, true))
+            .collect()
+    }
+
+    /// Looks up subtitles and audio tracks for a video in its folder, the configured asset
+    /// subfolders (recursively), sibling asset folders and shared ancestor folders.
+    pub fn find_matching_assets(&self, video_path: &Path, config: &Config) -> Vec<AssetMatch> {
+        let mut matches = Vec::new();
+
+        let Some(parent) = video_path.parent() else { return matches; };
+        let Some(video_stem) = video_path.file_stem().and_then(|s| s.to_str()) else { return matches; };
+
+        let video_fingerprint = get_fingerprint(video_stem);
+        if video_fingerprint.is_empty() { return matches; }
+        let video_episode = episode_key(video_stem);
+
+        let patterns = Self::folder_patterns(&config.asset_folders);
+        let search_dirs = self.search_dirs(parent, &patterns, true, config);
+        let search_dirs = search_dirs.into_iter().filter_map(|d| self.dirs.get(d));
+
+        for dir in search_dirs {
+            for idx in dir.candidates(&video_fingerprint, video_episode) {
+                let item = &dir.assets[idx];
+                if let Some(score) = match_score(&video_fingerprint, video_episode, item) {
+                    matches.push(AssetMatch { path: item.path.clone(), score, companions: item.companions.clone() });
                 }
             }
         }
+
+        matches
+    }
+}
+
+/// Splits a trailing part marker off a name: `Movie.2001.CD2` gives `Movie.2001` and 2.
+pub fn split_part(name: &str) -> Option<(&str, u32)> {
+    let caps = PART_RE.captures(name)?;
+    let base = caps.get(1)?.as_str();
+    if base.is_empty() {
+        return None;
     }
+    Some((base, caps[2].parse().ok()?))
+}
+
+/// Groups the videos into jobs, joining part sequences such as `Movie.CD1.avi` and
+/// `Movie.CD2.avi` into one job ordered by part number.
+///
+/// Only complete sequences starting at part 1 in the same folder are joined; anything
+/// ambiguous is left as single videos. Jobs keep the order of their first video.
+pub fn group_parts(videos: &[PathBuf]) -> Vec<Vec<PathBuf>> {
+    let mut sequences: HashMap<(PathBuf, String), Vec<(u32, usize)>> = HashMap::new();
+    for (idx, video) in videos.iter().enumerate() {
+        let stem = video.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let (name, _) = split_release(stem);
+        if let Some((base, part)) = split_part(&name) {
+            let parent = video.parent().map(Path::to_path_buf).unwrap_or_default();
+            sequences.entry((parent, base.to_lowercase())).or_default().push((part, idx));
+        }
+    }
+
+    // Index of the first video of each sequence -> the whole sequence
+    let mut joined: HashMap<usize, Vec<PathBuf>> = HashMap::new();
+    let mut grouped = HashSet::new();
+    for mut parts in sequences.into_values().filter(|p| p.len() > 1) {
+        parts.sort();
+        if parts.iter().enumerate().any(|(i, (part, _))| *part != i as u32 + 1) {
+            continue;
+        }
+        let first = parts.iter().map(|(_, idx)| *idx).min().unwrap_or(0);
+        grouped.extend(parts.iter().map(|(_, idx)| *idx));
+        joined.insert(first, parts.into_iter().map(|(_, idx)| videos[idx].clone()).collect());
+    }
+
+    videos.iter()
+        .enumerate()
+        .filter_map(|(idx, video)| match joined.remove(&idx) {
+            Some(parts) => Some(parts),
+            None if grouped.contains(&idx) => None,
+            None => Some(vec![video.clone()]),
+        })
+        .collect()
+}
 
-    assets
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(video: &str, assets: &[&str]) -> Vec<PathBuf> {
+        let config = Config::default();
+        let assets: Vec<PathBuf> = assets.iter().map(PathBuf::from).collect();
+        let index = AssetIndex::build(&assets, &config);
+        let mut found: Vec<PathBuf> = index.find_matching_assets(Path::new(video), &config)
+            .into_iter()
+            .map(|m| m.path)
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn asset_contained_in_video_name_matches() {
+        let found = matched("/in/[Group] Show - 01 [1080p].mkv", &["/in/Show - 01.srt", "/in/Show - 02.srt"]);
+        assert_eq!(found, vec![PathBuf::from("/in/Show - 01.srt")]);
+    }
+
+    #[test]
+    fn video_name_contained_in_asset_matches() {
+        let found = matched("/in/Show - 01.mkv", &["/in/[Group] Show - 01 [1080p].en.srt", "/in/Other.srt"]);
+        assert_eq!(found, vec![PathBuf::from("/in/[Group] Show - 01 [1080p].en.srt")]);
+    }
+
+    fn jobs(videos: &[&str]) -> Vec<Vec<PathBuf>> {
+        group_parts(&videos.iter().map(PathBuf::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn cd_parts_are_joined() {
+        let grouped = jobs(&["/in/Movie.2001.CD2.avi", "/in/Movie.2001.CD1.avi"]);
+        assert_eq!(grouped, vec![vec![PathBuf::from("/in/Movie.2001.CD1.avi"), PathBuf::from("/in/Movie.2001.CD2.avi")]]);
+    }
+
+    #[test]
+    fn part_in_title_is_not_joined() {
+        let grouped = jobs(&[
+            "/in/Harry.Potter.and.the.Deathly.Hallows.Part.1.mkv",
+            "/in/Harry.Potter.and.the.Deathly.Hallows.Part.2.mkv",
+        ]);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(split_part("Harry.Potter.and.the.Deathly.Hallows.Part.1"), None);
+    }
+
+    #[test]
+    fn part_marker_needs_a_separator() {
+        assert_eq!(split_part("Movie.2001.CD2"), Some(("Movie.2001", 2)));
+        assert_eq!(split_part("Movie [Disc 1]"), Some(("Movie", 1)));
+        assert_eq!(split_part("Abcd2"), None);
+    }
 }
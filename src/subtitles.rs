@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Result};
+use regex::{Captures, Regex};
+use once_cell::sync::Lazy;
+
+/// `00:01:02,345` SRT timestamps; some files use a dot instead of the comma.
+static SRT_TIME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d{2,}):(\d{2}):(\d{2})([,.])(\d{3})").unwrap());
+
+/// Start and end of an ASS/SSA `Dialogue:` or `Comment:` line: `Dialogue: 0,0:01:02.34,0:01:05.00,`
+static ASS_EVENT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^((?:Dialogue|Comment):\s*[^,]*,)(\d+):(\d{2}):(\d{2})\.(\d{2}),(\d+):(\d{2}):(\d{2})\.(\d{2}),").unwrap()
+});
+
+/// Text subtitle formats whose timestamps can be shifted and joined.
+pub fn is_joinable(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    matches!(ext.as_str(), "srt" | "ass" | "ssa")
+}
+
+fn read_text(path: &Path) -> Result<String> {
+    let bytes = fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.trim_start_matches('\u{feff}').replace("\r\n", "\n"))
+}
+
+fn shift_ms(ms: i64, offset_ms: i64) -> i64 {
+    (ms + offset_ms).max(0)
+}
+
+/// Shifts every SRT timestamp and renumbers the cues starting at `first_cue`.
+/// Returns the shifted text and the next free cue number.
+fn shift_srt(content: &str, offset_ms: i64, first_cue: usize) -> (String, usize) {
+    let mut out = String::new();
+    let mut cue = first_cue;
+
+    for block in content.split("\n\n").map(str::trim).filter(|b| !b.is_empty()) {
+        let mut lines = block.lines().peekable();
+        // Drop the original index, the cues are renumbered across all parts
+        if lines.peek().is_some_and(|l| l.trim().chars().all(|c| c.is_ascii_digit())) {
+            lines.next();
+        }
+
+        out.push_str(&format!("{}\n", cue));
+        for line in lines {
+            let line = SRT_TIME_RE.replace_all(line, |c: &Captures| {
+                let ms = ((c[1].parse::<i64>().unwrap_or(0) * 60 + c[2].parse::<i64>().unwrap_or(0)) * 60
+                    + c[3].parse::<i64>().unwrap_or(0)) * 1000 + c[5].parse::<i64>().unwrap_or(0);
+                let ms = shift_ms(ms, offset_ms);
+                format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, &c[4], ms % 1000)
+            });
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push('\n');
+        cue += 1;
+    }
+
+    (out, cue)
+}
+
+fn ass_time(c: &Captures, first: usize, offset_ms: i64) -> String {
+    let part = |i: usize| c[first + i].parse::<i64>().unwrap_or(0);
+    let ms = shift_ms(((part(0) * 60 + part(1)) * 60 + part(2)) * 1000 + part(3) * 10, offset_ms);
+    format!("{}:{:02}:{:02}.{:02}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000 / 10)
+}
+
+/// Shifts the start and end of every event line; other lines are returned unchanged.
+fn shift_ass_line(line: &str, offset_ms: i64) -> String {
+    ASS_EVENT_RE.replace(line, |c: &Captures| {
+        format!("{}{},{},", &c[1], ass_time(c, 2, offset_ms), ass_time(c, 6, offset_ms))
+    }).into_owned()
+}
+
+/// Joins ASS/SSA files: the header of the first file, styles missing from it taken from the
+/// later parts, and the events of every part shifted by its offset. The later events go to the
+/// end of the first file's `[Events]` block, since `[Fonts]` or `[Graphics]` may follow it.
+fn join_ass(parts: &[(String, i64)]) -> String {
+    let Some((first, first_offset)) = parts.first() else { return String::new() };
+
+    let style_name = |line: &str| line.strip_prefix("Style:").and_then(|s| s.split(',').next()).map(|s| s.trim().to_string());
+    let known: Vec<String> = first.lines().filter_map(style_name).collect();
+    let mut extra_styles: Vec<&str> = Vec::new();
+    for (content, _) in &parts[1..] {
+        for line in content.lines().filter(|l| l.starts_with("Style:")) {
+            let name = style_name(line).unwrap_or_default();
+            if !known.contains(&name) && !extra_styles.iter().any(|s| style_name(s) == Some(name.clone())) {
+                extra_styles.push(line);
+            }
+        }
+    }
+
+    let mut later_events = String::new();
+    for (content, offset) in &parts[1..] {
+        for line in content.lines().filter(|l| ASS_EVENT_RE.is_match(l)) {
+            later_events.push_str(&shift_ass_line(line, *offset));
+            later_events.push('\n');
+        }
+    }
+
+    let lines: Vec<&str> = first.lines().collect();
+    let events_at = match lines.iter().position(|l| l.trim().eq_ignore_ascii_case("[Events]")) {
+        Some(start) => {
+            let end = lines[start + 1..].iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            lines[start..end].iter().rposition(|l| !l.trim().is_empty()).map_or(end, |i| start + i + 1)
+        }
+        None => lines.len(),
+    };
+
+    let mut out = String::new();
+    let mut last_style_written = false;
+    for (i, line) in lines.iter().enumerate() {
+        if i == events_at {
+            out.push_str(&later_events);
+        }
+        out.push_str(&shift_ass_line(line, *first_offset));
+        out.push('\n');
+        // Add the missing styles right after the last style of the first file
+        let next_is_style = lines.get(i + 1).is_some_and(|l| l.starts_with("Style:"));
+        if line.starts_with("Style:") && !next_is_style && !last_style_written {
+            for style in &extra_styles {
+                out.push_str(style);
+                out.push('\n');
+            }
+            last_style_written = true;
+        }
+    }
+
+    if events_at == lines.len() {
+        out.push_str(&later_events);
+    }
+
+    out
+}
+
+/// Writes one subtitle file made of `parts`, each shifted by its offset in milliseconds.
+/// All parts must share the same format.
+pub fn join_subtitles(parts: &[(&Path, i64)], output: &Path) -> Result<()> {
+    let Some((first, _)) = parts.first() else { bail!("no subtitles to join") };
+    let ext = first.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+
+    let contents = parts.iter()
+        .map(|(path, offset)| Ok((read_text(path)?, *offset)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let joined = match ext.as_str() {
+        "srt" => {
+            let mut out = String::new();
+            let mut cue = 1;
+            for (content, offset) in &contents {
+                let (shifted, next) = shift_srt(content, *offset, cue);
+                out.push_str(&shifted);
+                cue = next;
+            }
+            out
+        }
+        "ass" | "ssa" => join_ass(&contents),
+        _ => bail!("cannot join .{} subtitles", ext),
+    };
+
+    fs::write(output, joined)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srt_is_shifted_and_renumbered() {
+        let content = "1\n00:00:01,000 --> 00:00:02,500\nHello\n\n2\n00:59:59.900 --> 01:00:00.100\nWorld\n";
+        let (shifted, next) = shift_srt(content, 1_500, 7);
+        assert_eq!(shifted, "7\n00:00:02,500 --> 00:00:04,000\nHello\n\n8\n01:00:01.400 --> 01:00:01.600\nWorld\n\n");
+        assert_eq!(next, 9);
+    }
+
+    #[test]
+    fn negative_offsets_stop_at_zero() {
+        let (shifted, _) = shift_srt("00:00:01,000 --> 00:00:03,000\nHi", -2_000, 1);
+        assert_eq!(shifted, "1\n00:00:00,000 --> 00:00:01,000\nHi\n\n");
+        assert_eq!(shift_ass_line("Dialogue: 0,0:00:01.00,0:00:05.50,Default,,0,0,0,,Hi", -2_000), "Dialogue: 0,0:00:00.00,0:00:03.50,Default,,0,0,0,,Hi");
+    }
+
+    #[test]
+    fn ass_events_are_joined_into_the_events_block() {
+        let first = "[Script Info]\nTitle: CD1\n\n[V4+ Styles]\nStyle: Default,Arial\n\n[Events]\nFormat: Layer, Start, End, Style, Text\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,One\n\n[Fonts]\nfontname: a.ttf\n";
+        let second = "[V4+ Styles]\nStyle: Default,Arial\nStyle: Sign,Arial\n\n[Events]\nDialogue: 0,0:00:03.00,0:00:04.00,Sign,,0,0,0,,Two\n";
+        let joined = join_ass(&[(first.to_string(), 0), (second.to_string(), 60_000)]);
+        assert_eq!(
+            joined,
+            "[Script Info]\nTitle: CD1\n\n[V4+ Styles]\nStyle: Default,Arial\nStyle: Sign,Arial\n\n[Events]\nFormat: Layer, Start, End, Style, Text\n\
+             Dialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,One\nDialogue: 0,0:01:03.00,0:01:04.00,Sign,,0,0,0,,Two\n\n[Fonts]\nfontname: a.ttf\n"
+        );
+    }
+
+    #[test]
+    fn ass_events_are_appended_when_events_come_last() {
+        let first = "[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,One\n";
+        let second = "[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Two\n";
+        let joined = join_ass(&[(first.to_string(), 0), (second.to_string(), 1_000)]);
+        assert_eq!(joined, "[Events]\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,One\nDialogue: 0,0:00:02.00,0:00:03.00,Default,,0,0,0,,Two\n");
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use crate::config::Config;
+
+/// eac3to-style delay tags, e.g. `DELAY 120ms` or `delay -42ms`.
+static DELAY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)delay[ ._]*(-?\d+)\s*ms").unwrap());
+
+/// Reads the per-directory sidecar mapping file names to offsets in milliseconds.
+fn read_sidecar(path: &Path) -> HashMap<String, i64> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the sync offset in milliseconds for an audio or subtitle file.
+///
+/// An entry in the sidecar file next to the asset wins over a delay tag in its name.
+pub fn sync_offset(path: &Path, config: &Config) -> Option<i64> {
+    let name = path.file_name()?.to_str()?;
+
+    if let Some(dir) = path.parent() {
+        let sidecar = read_sidecar(&dir.join(&config.sync_sidecar_name));
+        if let Some(ms) = sidecar.get(name) {
+            return Some(*ms);
+        }
+    }
+
+    let caps = DELAY_RE.captures(name)?;
+    caps[1].parse().ok().filter(|ms| *ms != 0)
+}
@@ -0,0 +1,65 @@
+use crate::processor::MediaInfo;
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// One `<Tag>` block for the given target level (70 = collection, 60 = season, 50 = movie/episode).
+fn tag_block(target: u32, simple: &[(&str, String)]) -> String {
+    let mut xml = format!("  <Tag>\n    <Targets><TargetTypeValue>{}</TargetTypeValue></Targets>\n", target);
+    for (name, value) in simple {
+        xml.push_str(&format!(
+            "    <Simple><Name>{}</Name><String>{}</String></Simple>\n",
+            name,
+            escape_xml(value)
+        ));
+    }
+    xml.push_str("  </Tag>\n");
+    xml
+}
+
+/// Builds a Matroska global tags document from the parsed media info.
+///
+/// Movies get TITLE and DATE_RELEASED on the movie level. Episodes get the show TITLE (and the
+/// show's year) on the collection level, the season as PART_NUMBER (plus TOTAL_PARTS when
+/// known) on the season level and the episode as PART_NUMBER (or its air date) on the episode level.
+pub fn global_tags_xml(meta: &MediaInfo, season_total: Option<u32>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE Tags SYSTEM \"matroskatags.dtd\">\n<Tags>\n");
+
+    if meta.is_series {
+        let mut show_tags = vec![("TITLE", meta.title.clone())];
+        if let (Some(year), None) = (&meta.year, &meta.air_date) {
+            show_tags.push(("DATE_RELEASED", year.clone()));
+        }
+        xml.push_str(&tag_block(70, &show_tags));
+
+        if let Some(season) = meta.season {
+            let mut season_tags = vec![("PART_NUMBER", season.to_string())];
+            if let Some(total) = season_total {
+                season_tags.push(("TOTAL_PARTS", total.to_string()));
+            }
+            xml.push_str(&tag_block(60, &season_tags));
+        }
+
+        let mut episode_tags = vec![("TITLE", meta.segment_title())];
+        if let Some(episode) = meta.episode() {
+            episode_tags.push(("PART_NUMBER", episode.to_string()));
+        }
+        if let Some(date) = &meta.air_date {
+            episode_tags.push(("DATE_RELEASED", date.clone()));
+        }
+        xml.push_str(&tag_block(50, &episode_tags));
+    } else {
+        let mut movie_tags = vec![("TITLE", meta.title.clone())];
+        if let Some(year) = &meta.year {
+            movie_tags.push(("DATE_RELEASED", year.clone()));
+        }
+        xml.push_str(&tag_block(50, &movie_tags));
+    }
+
+    xml.push_str("</Tags>\n");
+    xml
+}